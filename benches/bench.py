@@ -0,0 +1,51 @@
+"""Measure provider call overhead of the installed ``inj`` module.
+
+    cargo build --release
+    cp target/release/libinj.so inj.so
+    PYTHONPATH=. python benches/bench.py [--number N] [--repeat N] [--json]
+
+Cases are defined in ``cases.py`` and shared with the criterion suite, the
+best of ``--repeat`` runs is reported per call.
+"""
+
+import argparse
+import json
+import os
+import sys
+import timeit
+
+sys.path.append(os.path.dirname(os.path.abspath(__file__)))
+
+from cases import CASES  # noqa: E402
+
+#: Calls per run of the slow cases
+SLOW_CASES = {"from_schema_500": 100}
+
+
+def measure(number, repeat):
+    results = {}
+    for name, case in CASES.items():
+        calls = min(number, SLOW_CASES.get(name, number))
+        best = min(timeit.repeat(case, number=calls, repeat=repeat))
+        results[name] = best / calls * 1e9
+    return results
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.splitlines()[0])
+    parser.add_argument("--number", type=int, default=100_000, help="calls per run")
+    parser.add_argument("--repeat", type=int, default=5, help="runs per case")
+    parser.add_argument("--json", action="store_true", help="print results as JSON")
+    options = parser.parse_args()
+
+    results = measure(options.number, options.repeat)
+    if options.json:
+        print(json.dumps(results, indent=2))
+        return
+    width = max(map(len, results))
+    for name, nanoseconds in results.items():
+        print(f"{name:<{width}}  {nanoseconds:>12.1f} ns")
+
+
+if __name__ == "__main__":
+    main()
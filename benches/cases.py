@@ -0,0 +1,102 @@
+"""Benchmark cases shared by the criterion suite and ``bench.py``.
+
+Every case is a callable without arguments performing the measured call,
+``CASES`` maps case names to them.
+"""
+
+import sys
+import types
+
+import inj
+from inj.wiring import Provide, inject
+
+
+class Service:
+    def __init__(self, *args, **kwargs):
+        self.args = args
+        self.kwargs = kwargs
+
+
+def factory(injections):
+    """Factory with half positional and half keyword provider injections."""
+    dependency = inj.Object(1)
+    args = [dependency] * (injections // 2)
+    kwargs = {f"kw{i}": dependency for i in range(injections - injections // 2)}
+    return inj.Factory(Service, *args, **kwargs)
+
+
+def singleton():
+    provider = inj.Singleton(Service)
+    provider()
+    return provider
+
+
+def overridden():
+    provider = inj.Factory(Service)
+    provider.override(inj.Factory(Service, inj.Object(1)))
+    return provider
+
+
+def nested(depth):
+    """Return factory of a container ``depth`` containers deep."""
+
+    class Leaf(inj.DeclarativeContainer):
+        service = inj.Factory(Service, inj.Object(1))
+
+    cls = Leaf
+    for _ in range(depth):
+
+        class Level(inj.DeclarativeContainer):
+            child = inj.Container(cls)
+
+        cls = Level
+    provider = cls()
+    for _ in range(depth):
+        provider = provider.child
+    return provider.service
+
+
+def injected():
+    container = inj.DynamicContainer()
+    container.service = inj.Factory(Service, inj.Object(1))
+    module = types.ModuleType("inj_bench_wired")
+    sys.modules[module.__name__] = module
+
+    @inject
+    def handler(service=Provide[container.service]):
+        return service
+
+    module.handler = handler
+    container.wire(modules=[module])
+    return module.handler
+
+
+def schema(size):
+    """Schema of ``size`` factories, each depending on the previous one."""
+    providers = {}
+    for i in range(size):
+        kwargs = {"previous": f"container.p{i - 1}"} if i else {}
+        providers[f"p{i}"] = {
+            "provider": "Factory",
+            "provides": "builtins.dict",
+            "args": [],
+            "kwargs": kwargs,
+        }
+    return {"version": "1", "container": providers}
+
+
+def from_schema(size):
+    document = schema(size)
+    return lambda: inj.DynamicContainer().from_schema(document)
+
+
+CASES = {
+    "factory_0": factory(0),
+    "factory_5": factory(5),
+    "factory_20": factory(20),
+    "singleton_hit": singleton(),
+    "overridden": overridden(),
+    "nested_containers_10": nested(10),
+    "inject_call": injected(),
+    "from_schema_500": from_schema(500),
+}
@@ -0,0 +1,43 @@
+//! Provider call overhead, measured on the cases of `cases.py`.
+//!
+//! The interpreter is embedded, so libpython has to be linked:
+//!
+//! ```sh
+//! cargo bench --no-default-features --features python
+//! ```
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyModule};
+
+const CASES: &str = include_str!("cases.py");
+
+/// Cases too slow for the default number of samples
+const SLOW_CASES: &[&str] = &["from_schema_500"];
+
+fn load_cases(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let module = PyModule::from_code_bound(py, CASES, "cases.py", "cases")?;
+    Ok(module.getattr("CASES")?.downcast_into()?)
+}
+
+fn providers(c: &mut Criterion) {
+    inj::append_to_inittab();
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let cases = load_cases(py).unwrap_or_else(|err| {
+            err.print(py);
+            panic!("failed to load benchmark cases")
+        });
+        let mut group = c.benchmark_group("providers");
+        for (name, case) in cases.iter() {
+            let name: String = name.extract().unwrap();
+            let slow = SLOW_CASES.contains(&name.as_str());
+            group.sample_size(if slow { 10 } else { 100 });
+            group.bench_function(&name, |b| b.iter(|| case.call0().unwrap()));
+        }
+        group.finish();
+    });
+}
+
+criterion_group!(benches, providers);
+criterion_main!(benches);
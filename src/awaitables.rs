@@ -0,0 +1,285 @@
+use pyo3::exceptions::{PyRuntimeError, PyStopIteration};
+use pyo3::intern;
+use pyo3::prelude::*;
+use pyo3::types::{PyList, PyType};
+
+/// Next step of an awaitable state machine
+pub enum Step {
+    /// Await another awaitable, resuming with its result
+    Await(PyObject),
+    /// Finish with the value
+    Return(PyObject),
+}
+
+/// State machine driven by `Awaitable`.
+///
+/// It is resumed with `None` on start and then with the result of every
+/// awaitable it asked to await.
+pub trait Resume: Send {
+    fn resume(&mut self, py: Python<'_>, value: PyResult<PyObject>) -> PyResult<Step>;
+
+    /// Called when the awaitable is closed before it has finished
+    fn close(&mut self, _py: Python<'_>) {}
+}
+
+impl<F> Resume for F
+where
+    F: FnMut(Python<'_>, PyResult<PyObject>) -> PyResult<Step> + Send,
+{
+    fn resume(&mut self, py: Python<'_>, value: PyResult<PyObject>) -> PyResult<Step> {
+        self(py, value)
+    }
+}
+
+enum Input {
+    Send(PyObject),
+    Throw(PyErr),
+}
+
+/// Python awaitable implemented with a Rust state machine
+#[pyclass(module = "inj")]
+pub struct Awaitable {
+    state: Box<dyn Resume>,
+    current: Option<PyObject>,
+    finished: bool,
+}
+
+impl Awaitable {
+    pub fn new(state: impl Resume + 'static) -> Self {
+        Self {
+            state: Box::new(state),
+            current: None,
+            finished: false,
+        }
+    }
+
+    /// Awaitable finishing right away with the value
+    pub fn ready(value: PyObject) -> Self {
+        let mut value = Some(value);
+        Self::new(move |py: Python<'_>, _: PyResult<PyObject>| {
+            Ok(Step::Return(value.take().unwrap_or_else(|| py.None())))
+        })
+    }
+
+    /// Await the awaitable and map its outcome
+    pub fn map<F>(awaitable: PyObject, f: F) -> Self
+    where
+        F: FnOnce(Python<'_>, PyResult<PyObject>) -> PyResult<PyObject> + Send + 'static,
+    {
+        let mut awaitable = Some(awaitable);
+        let mut f = Some(f);
+        Self::new(move |py: Python<'_>, value: PyResult<PyObject>| {
+            if let Some(awaitable) = awaitable.take() {
+                return Ok(Step::Await(awaitable));
+            }
+            match f.take() {
+                Some(f) => Ok(Step::Return(f(py, value)?)),
+                None => Err(PyRuntimeError::new_err("awaitable is already finished")),
+            }
+        })
+    }
+
+    fn drive(&mut self, py: Python<'_>, mut input: Input) -> PyResult<PyObject> {
+        if self.finished {
+            return Err(PyRuntimeError::new_err(
+                "cannot reuse already awaited awaitable",
+            ));
+        }
+        loop {
+            let step = match (self.current.take(), input) {
+                (Some(iterator), input) => {
+                    let iterator = iterator.into_bound(py);
+                    let result = match input {
+                        Input::Send(value) => iterator.call_method1(intern!(py, "send"), (value,)),
+                        Input::Throw(err) => {
+                            iterator.call_method1(intern!(py, "throw"), (err.into_value(py),))
+                        }
+                    };
+                    match result {
+                        Ok(yielded) => {
+                            self.current = Some(iterator.unbind());
+                            return Ok(yielded.unbind());
+                        }
+                        Err(err) if err.is_instance_of::<PyStopIteration>(py) => {
+                            let value = err.value_bound(py).getattr(intern!(py, "value"))?;
+                            self.state.resume(py, Ok(value.unbind()))
+                        }
+                        Err(err) => self.state.resume(py, Err(err)),
+                    }
+                }
+                (None, Input::Send(_)) => self.state.resume(py, Ok(py.None())),
+                (None, Input::Throw(err)) => self.state.resume(py, Err(err)),
+            };
+            match step {
+                Ok(Step::Await(awaitable)) => {
+                    self.current = Some(awaitable.call_method0(py, intern!(py, "__await__"))?);
+                    input = Input::Send(py.None());
+                }
+                Ok(Step::Return(value)) => {
+                    self.finished = true;
+                    return Err(PyStopIteration::new_err((value,)));
+                }
+                Err(err) => {
+                    self.finished = true;
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+#[pymethods]
+impl Awaitable {
+    fn __await__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python) -> PyResult<PyObject> {
+        self.drive(py, Input::Send(py.None()))
+    }
+
+    fn send(&mut self, py: Python, value: PyObject) -> PyResult<PyObject> {
+        self.drive(py, Input::Send(value))
+    }
+
+    #[pyo3(signature = (typ, val=None, _tb=None))]
+    fn throw(
+        &mut self,
+        py: Python,
+        typ: &Bound<'_, PyAny>,
+        val: Option<&Bound<'_, PyAny>>,
+        _tb: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<PyObject> {
+        let err = if typ.is_instance_of::<PyType>() {
+            match val {
+                Some(val) if val.is_instance(typ)? => PyErr::from_value_bound(val.clone()),
+                Some(val) if !val.is_none() => PyErr::from_value_bound(typ.call1((val,))?),
+                _ => PyErr::from_value_bound(typ.call0()?),
+            }
+        } else {
+            PyErr::from_value_bound(typ.clone())
+        };
+        self.drive(py, Input::Throw(err))
+    }
+
+    fn close(&mut self, py: Python) -> PyResult<()> {
+        let finished = std::mem::replace(&mut self.finished, true);
+        if !finished {
+            self.state.close(py);
+        }
+        if let Some(iterator) = self.current.take() {
+            let iterator = iterator.bind(py);
+            if iterator.hasattr(intern!(py, "close"))? {
+                iterator.call_method0(intern!(py, "close"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check if object can be awaited
+pub fn is_awaitable(obj: &Bound<'_, PyAny>) -> bool {
+    obj.get_type()
+        .hasattr(intern!(obj.py(), "__await__"))
+        .unwrap_or(false)
+}
+
+/// Await awaitables concurrently, returning the list of their results.
+///
+/// Awaitables are scheduled as tasks on the first resume and awaited in
+/// order. Remaining tasks are cancelled on the first failure.
+pub fn gather(awaitables: Vec<PyObject>) -> Awaitable {
+    Awaitable::new(Gather {
+        awaitables,
+        tasks: Vec::new(),
+        results: Vec::new(),
+    })
+}
+
+struct Gather {
+    awaitables: Vec<PyObject>,
+    tasks: Vec<PyObject>,
+    results: Vec<PyObject>,
+}
+
+impl Gather {
+    fn cancel(&self, py: Python<'_>) -> PyResult<()> {
+        for task in &self.tasks[self.results.len()..] {
+            task.call_method0(py, intern!(py, "cancel"))?;
+        }
+        Ok(())
+    }
+}
+
+impl Resume for Gather {
+    fn resume(&mut self, py: Python<'_>, value: PyResult<PyObject>) -> PyResult<Step> {
+        if self.tasks.is_empty() && !self.awaitables.is_empty() {
+            let ensure_future = py
+                .import_bound(intern!(py, "asyncio"))?
+                .getattr(intern!(py, "ensure_future"))?;
+            for awaitable in self.awaitables.drain(..) {
+                self.tasks.push(ensure_future.call1((awaitable,))?.unbind());
+            }
+        } else {
+            match value {
+                Ok(value) => self.results.push(value),
+                Err(err) => {
+                    self.cancel(py)?;
+                    return Err(err);
+                }
+            }
+        }
+        match self.tasks.get(self.results.len()) {
+            Some(task) => Ok(Step::Await(task.clone_ref(py))),
+            None => {
+                let results = std::mem::take(&mut self.results);
+                Ok(Step::Return(
+                    PyList::new_bound(py, results).into_any().unbind(),
+                ))
+            }
+        }
+    }
+}
+
+/// Futures of concurrent awaits waiting for a shared result.
+///
+/// Only plain event loop futures are created, one per waiter; the result
+/// is set on them when the owner finishes.
+#[derive(Default)]
+pub struct Waiters {
+    futures: Vec<PyObject>,
+}
+
+impl Waiters {
+    /// Return future of the running loop resolved by `wake()`
+    pub fn wait(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        let future = py
+            .import_bound(intern!(py, "asyncio"))?
+            .call_method0(intern!(py, "get_running_loop"))?
+            .call_method0(intern!(py, "create_future"))?
+            .unbind();
+        self.futures.push(future.clone_ref(py));
+        Ok(future)
+    }
+
+    /// Resolve waiting futures with the result
+    pub fn wake(self, py: Python<'_>, result: &PyResult<PyObject>) -> PyResult<()> {
+        for future in self.futures {
+            let future = future.bind(py);
+            if future.call_method0(intern!(py, "done"))?.is_truthy()? {
+                continue;
+            }
+            match result {
+                Ok(value) => future.call_method1(intern!(py, "set_result"), (value,))?,
+                Err(err) => {
+                    future.call_method1(intern!(py, "set_exception"), (err.value_bound(py),))?
+                }
+            };
+        }
+        Ok(())
+    }
+}
@@ -1,17 +1,13 @@
-use crate::{providers, schema};
+use crate::{awaitables, errors, graph, providers, schema, scopes, tracing, wiring};
 use pyo3::exceptions::{PyAttributeError, PyRuntimeError};
 use pyo3::intern;
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyIterator, PyTuple, PyType};
+use pyo3::sync::GILOnceCell;
+use pyo3::types::{PyCFunction, PyDict, PyIterator, PyModule, PyString, PyTuple, PyType};
 use pyo3::{PyTypeCheck, PyTypeInfo};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 
-// use py_async_futures::futures::future::Future;
-// use py_async_futures::futures::FutureExt;
-// use py_async_futures::futures::StreamExt;
-// use py_async_futures::tokio::stream;
-// use py_async_futures::tokio::sync::mpsc;
 use pyo3::{PyAny, PyResult, Python};
 
 #[pyclass]
@@ -39,8 +35,8 @@ impl WiringConfiguration {
         auto_wire: bool,
     ) -> Self {
         Self {
-            modules: modules.unwrap_or_else(Vec::new),
-            packages: packages.unwrap_or_else(Vec::new),
+            modules: modules.unwrap_or_default(),
+            packages: packages.unwrap_or_default(),
             from_package,
             auto_wire,
         }
@@ -59,7 +55,7 @@ impl Default for WiringConfiguration {
 }
 
 /// Base class for containers
-#[pyclass(subclass, dict)]
+#[pyclass(subclass, dict, weakref)]
 pub struct Container {
     attributes: HashMap<String, PyObject>,
 }
@@ -103,7 +99,7 @@ pub struct DynamicContainer {
     #[pyo3(get)]
     pub overridden: Vec<Py<PyAny>>,
     #[pyo3(get)]
-    pub parent: Option<Py<Self>>,
+    pub parent: Option<PyObject>,
     #[pyo3(get)]
     pub declarative_parent: Option<Py<PyType>>,
     #[pyo3(get)]
@@ -135,52 +131,96 @@ impl DynamicContainer {
         (this, base)
     }
 
-    // fn __deepcopy__(&self, memo: &mut HashMap<usize, Py<PyAny>>) -> PyResult<Py<PyAny>> {
-    //     if let Some(copied) = memo.get(&self.id()) {
-    //         return Ok(copied.clone());
-    //     }
-    //
-    //     let copied = Self::new()?;
-    //     memo.insert(self.id(), copied.clone());
-    //
-    //     // copied.__self__ = providers::deepcopy(&self.__self__, memo)?;
-    //     // for name in copied.__self__.alt_names.iter() {
-    //     //     copied.set_provider(name, copied.__self__.clone())?;
-    //     // }
-    //
-    //     copied.provider_type = PyType::from_type::<providers::Provider>();
-    //     copied.overridden = providers::deepcopy(&self.overridden, memo)?;
-    //     copied.wiring_config = copy_module::deepcopy(&self.wiring_config, memo)?;
-    //     copied.declarative_parent = self.declarative_parent.clone();
-    //
-    //     for (name, provider) in providers::deepcopy(&self.providers, memo)?.iter() {
-    //         copied.set_provider(name, provider)?;
-    //     }
-    //
-    //     copied.parent = providers::deepcopy(&self.parent, memo)?;
-    //
-    //     Ok(copied.into())
-    // }
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let py = slf.py();
+        if let Some(copied) = providers::memo_get(slf.as_any(), memo)? {
+            return Ok(copied);
+        }
+        let copied = slf.get_type().call0()?;
+        providers::memo_set(slf.as_any(), &copied, memo)?;
+        let copied_container = copied.downcast::<Self>()?;
+
+        let (container_providers, overridden, parent) = {
+            let this = slf.borrow();
+            let mut copied = copied_container.borrow_mut();
+            copied.provider_type = this.provider_type.clone_ref(py);
+            copied.wiring_config = this.wiring_config.clone();
+            copied.declarative_parent = this.declarative_parent.clone();
+            (
+                this.providers.clone(),
+                this.overridden.clone(),
+                this.parent.clone(),
+            )
+        };
+
+        let overridden = providers::deepcopy(overridden.into_py(py).bind(py), memo)?.extract()?;
+        copied_container.borrow_mut().overridden = overridden;
+
+        let container_providers =
+            providers::deepcopy(container_providers.into_py(py).bind(py), memo)?;
+        for (name, provider) in container_providers.downcast::<PyDict>()?.iter() {
+            Self::set_provider(copied_container, name.extract()?, provider.downcast()?)?;
+        }
+
+        let self_providers: Vec<(String, PyObject)> = {
+            let this = slf.borrow();
+            let base: &Container = this.as_ref();
+            base.attributes
+                .iter()
+                .filter(|(_, value)| value.bind(py).is_instance_of::<providers::SelfProvider>())
+                .map(|(name, value)| (name.clone(), value.clone_ref(py)))
+                .collect()
+        };
+        for (name, self_provider) in self_providers {
+            let self_provider = providers::deepcopy(self_provider.bind(py), memo)?;
+            Self::__setattr__(copied_container, name, &self_provider)?;
+        }
+
+        let parent = providers::copy_parent(parent, memo)?;
+        copied_container.borrow_mut().parent = parent;
+        Ok(copied)
+    }
 
     /// Set instance attribute.
     ///
     /// If value of attribute is provider, it will be added into providers
     /// dictionary.
-    fn __setattr__(&mut self, py: Python, name: String, value: &Bound<'_, PyAny>) -> PyResult<()> {
-        if providers::Provider::type_check(value) && name != "parent" {
-            self.check_provider_type(py, value)?;
-            self.providers.insert(
-                name.clone(),
-                value
-                    .downcast::<providers::Provider>()
-                    .map(|p| p.clone().unbind())?,
-            );
-
-            // if isinstance(value, providers.CHILD_PROVIDERS):
-            //     value.assign_parent(self)
-        }
-        // let mut super_ = self_.into_super();
-        // super_.__setattr__(py, name, value)?;
+    fn __setattr__(slf: &Bound<'_, Self>, name: String, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        let py = slf.py();
+        let is_provider = providers::Provider::type_check(value)
+            && !value.is_instance_of::<providers::SelfProvider>()
+            && name != "parent";
+        if let Ok(self_provider) = value.downcast::<providers::SelfProvider>() {
+            let mut self_provider = self_provider.borrow_mut();
+            if self_provider.container.is_none() {
+                self_provider.set_container(slf.clone().into_any().unbind());
+            }
+        }
+        if is_provider {
+            Self::check_provider_type(slf, value)?;
+        }
+        {
+            let mut this = slf.borrow_mut();
+            if is_provider {
+                providers::Provider::set_name(value, &name);
+                providers::Provider::set_owner(value, slf.as_any())?;
+                this.providers.insert(
+                    name.clone(),
+                    value
+                        .downcast::<providers::Provider>()
+                        .map(|p| p.clone().unbind())?,
+                );
+            }
+            let base: &mut Container = this.as_mut();
+            base.__setattr__(py, name, value)?;
+        }
+
+        if is_provider && providers::is_child_provider(value)? {
+            value.call_method1(intern!(py, "assign_parent"), (slf,))?;
+        }
         Ok(())
     }
 
@@ -228,33 +268,31 @@ impl DynamicContainer {
         py: Python<'py>,
         types: Option<Vec<Py<PyType>>>,
     ) -> PyResult<Bound<'py, PyIterator>> {
-        let providers: Vec<_> = self.providers.values().map(|p| p.clone()).collect();
+        let providers: Vec<_> = self.providers.values().cloned().collect();
         providers::traverse(py, providers, types)
     }
 
     /// Set container providers
     #[pyo3(signature = (**providers))]
     fn set_providers(
-        &mut self,
-        py: Python,
+        slf: &Bound<'_, Self>,
         providers: Option<HashMap<String, Bound<'_, providers::Provider>>>,
     ) -> PyResult<()> {
         if let Some(providers) = providers {
             for (name, provider) in providers.iter() {
-                self.__setattr__(py, name.clone(), provider)?;
+                Self::__setattr__(slf, name.clone(), provider)?;
             }
         }
         Ok(())
     }
 
-    /// Set container provider  
+    /// Set container provider
     fn set_provider(
-        &mut self,
-        py: Python,
+        slf: &Bound<'_, Self>,
         name: String,
         provider: &Bound<'_, providers::Provider>,
     ) -> PyResult<()> {
-        self.__setattr__(py, name, provider)
+        Self::__setattr__(slf, name, provider)
     }
 
     /// Override current container by overriding container
@@ -344,113 +382,200 @@ impl DynamicContainer {
     fn is_auto_wiring_enabled(&self) -> bool {
         self.wiring_config.auto_wire
     }
-    //
-    // fn wire(
-    //     &mut self,
-    //     modules: Option<Vec<String>>,
-    //     packages: Option<Vec<String>>,
-    //     from_package: Option<String>,
-    // ) -> PyResult<()> {
-    //     let modules = modules.unwrap_or_else(|| self.wiring_config.modules.clone());
-    //     let packages = packages.unwrap_or_else(|| self.wiring_config.packages.clone());
-    //
-    //     let modules = modules.iter().map(|m| _resolve_string_imports(m, &from_package)).flatten().collect::<Vec<String>>();
-    //     let packages = packages.iter().map(|p| _resolve_string_imports(p, &from_package)).flatten().collect::<Vec<String>>();
-    //
-    //     if modules.is_empty() && packages.is_empty() {
-    //         return Ok(());
-    //     }
-    //
-    //     wire(self, &modules, &packages)?;
-    //
-    //     if !modules.is_empty() {
-    //         self.wired_to_modules.extend(modules);
-    //     }
-    //     if !packages.is_empty() {
-    //         self.wired_to_packages.extend(packages);
-    //     }
-    //
-    //     Ok(())
-    // }
-    //
-    // fn unwire(&mut self) -> PyResult<()> {
-    //     unwire(&self.wired_to_modules, &self.wired_to_packages)?;
-    //
-    //     self.wired_to_modules.clear();
-    //     self.wired_to_packages.clear();
-    //
-    //     Ok(())
-    // }
-    //
-    // fn init_resources(&mut self) -> PyResult<()> {
-    //     let futures = self
-    //         .traverse(Some(vec![self.provider_type.clone()]))?
-    //         .map(|provider| {
-    //             let provider = provider.downcast::<providers::Resource>()?;
-    //             provider.init()
-    //         })
-    //         .collect::<Vec<_>>();
-    //     if !futures.is_empty() {
-    //         Ok(asyncio::gather(futures))
-    //     } else {
-    //         Ok(())
-    //     }
-    // }
-    //
-    // fn shutdown_resources(&mut self) -> PyResult<()> {
-    //     let independent_resources = |resources: &mut [Py<providers::Resource>], initialized: &mut [bool]| -> PyIter<Py<providers::Resource>> {
-    //         for resource in resources.iter() {
-    //             for other_resource in resources.iter() {
-    //                 if !other_resource.initialized {
-    //                     continue;
-    //                 }
-    //                 if resource.related.contains(&other_resource) {
-    //                     break;
-    //                 }
-    //             }
-    //             if !initialized.contains(&true) {
-    //                 yield resource.clone();
-    //             }
-    //         }
-    //     };
-    //
-    //     let async_ordered_shutdown = |resources: &mut [Py<providers::Resource>], initialized: &mut [bool]| -> PyResult<()> {
-    //         while initialized.contains(&true) {
-    //             let resources_to_shutdown = independent_resources(resources, initialized).collect::<Vec<_>>();
-    //             if resources_to_shutdown.is_empty() {
-    //                 return Err(PyErr::new::<RuntimeError, _>("Unable to resolve resources shutdown order"));
-    //             }
-    //             let futures = resources_to_shutdown.iter().map(|resource| resource.shutdown()).collect::<Vec<_>>();
-    //             asyncio::gather(futures).map_err(|e| PyErr::from(e))?;
-    //         }
-    //         Ok(())
-    //     };
-    //
-    //     let sync_ordered_shutdown = |resources: &mut [Py<providers::Resource>], initialized: &mut [bool]| -> PyResult<()> {
-    //         while initialized.contains(&true) {
-    //             let resources_to_shutdown = independent_resources(resources, initialized).collect::<Vec<_>>();
-    //             if resources_to_shutdown.is_empty() {
-    //                 return Err(PyErr::new::<RuntimeError, _>("Unable to resolve resources shutdown order"));
-    //             }
-    //             for resource in resources_to_shutdown {
-    //                 resource.shutdown();
-    //             }
-    //         }
-    //         Ok(())
-    //     };
-    //
-    //     let mut resources = self
-    //         .traverse(Some(vec![self.provider_type.clone()]))?
-    //         .map(|provider| provider.downcast::<providers::Resource>().unwrap())
-    //         .collect::<Vec<_>>();
-    //     let mut initialized = vec![false; resources.len()];
-    //
-    //     if resources.iter().any(|resource| resource.is_async_mode_enabled()) {
-    //         async_ordered_shutdown(&mut resources, &mut initialized)
-    //     } else {
-    //         sync_ordered_shutdown(&mut resources, &mut initialized)
-    //     }
-    // }
+
+    /// Wire container providers with provided packages and modules.
+    ///
+    /// Relative module names are resolved against `from_package`, which
+    /// defaults to the package of the declarative container or of the caller.
+    #[pyo3(signature = (modules=None, packages=None, from_package=None))]
+    fn wire(
+        slf: &Bound<'_, Self>,
+        modules: Option<Vec<Bound<'_, PyAny>>>,
+        packages: Option<Vec<Bound<'_, PyAny>>>,
+        from_package: Option<String>,
+    ) -> PyResult<()> {
+        let py = slf.py();
+        let (modules, packages, from_package) = {
+            let this = slf.borrow();
+            let from_config = |names: &[String]| -> Vec<Bound<'_, PyAny>> {
+                names
+                    .iter()
+                    .map(|name| PyString::new_bound(py, name).into_any())
+                    .collect()
+            };
+            (
+                modules.unwrap_or_else(|| from_config(&this.wiring_config.modules)),
+                packages.unwrap_or_else(|| from_config(&this.wiring_config.packages)),
+                from_package.or_else(|| this.wiring_config.from_package.clone()),
+            )
+        };
+        if modules.is_empty() && packages.is_empty() {
+            return Ok(());
+        }
+
+        let from_package = match from_package {
+            Some(from_package) => Some(from_package),
+            None => slf.borrow().resolve_package_name(py)?,
+        };
+        let modules = modules
+            .iter()
+            .map(|module| resolve_module(module, from_package.as_deref()))
+            .collect::<PyResult<Vec<_>>>()?;
+        let packages = packages
+            .iter()
+            .map(|package| resolve_module(package, from_package.as_deref()))
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let mut wired_modules = modules.clone();
+        for package in &packages {
+            wired_modules.extend(wiring::walk_package(package)?);
+        }
+        wiring::wire(slf, &wired_modules)?;
+
+        let mut this = slf.borrow_mut();
+        for module in &modules {
+            this.wired_to_modules.push(module.name()?.to_string());
+        }
+        for package in &packages {
+            this.wired_to_packages.push(package.name()?.to_string());
+        }
+        Ok(())
+    }
+
+    /// Unwire container providers from previously wired packages and modules.
+    fn unwire(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let (module_names, package_names) = {
+            let mut this = slf.borrow_mut();
+            (
+                std::mem::take(&mut this.wired_to_modules),
+                std::mem::take(&mut this.wired_to_packages),
+            )
+        };
+
+        let mut modules = Vec::new();
+        for name in &module_names {
+            modules.push(wiring::import_module(py, name, None)?);
+        }
+        for name in &package_names {
+            modules.extend(wiring::walk_package(&wiring::import_module(
+                py, name, None,
+            )?)?);
+        }
+        wiring::unwire(&modules)
+    }
+
+    /// Check providers for circular dependencies.
+    ///
+    /// Raises `CircularDependencyError` with the dependency path, e.g.
+    /// ``a -> b -> c -> a``. Delegated providers are not followed, as
+    /// they are injected without being called.
+    fn validate(&self, py: Python) -> PyResult<()> {
+        let mut names = HashMap::new();
+        let mut roots: Vec<(&String, &Py<providers::Provider>)> = self.providers.iter().collect();
+        roots.sort_by_key(|(name, _)| *name);
+        for (name, provider) in &roots {
+            names.insert(provider.as_ptr() as usize, (*name).clone());
+        }
+
+        let mut done = HashSet::new();
+        for (_, provider) in roots {
+            let mut path = Vec::new();
+            if let Some(cycle) = find_cycle(provider.bind(py).as_any(), &mut path, &mut done)? {
+                return Err(providers::circular_dependency_error(
+                    &cycle,
+                    |provider| match names.get(&(provider.as_ptr() as usize)) {
+                        Some(name) => Ok(name.clone()),
+                        None => providers::provider_label(provider),
+                    },
+                )?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Return dependency graph of the container providers.
+    ///
+    /// Graph can be rendered with `to_dot()`, `to_mermaid()` or `to_json()`.
+    fn graph(slf: &Bound<'_, Self>) -> PyResult<graph::Graph> {
+        let py = slf.py();
+        let mut roots: Vec<(String, PyObject)> = slf
+            .borrow()
+            .providers
+            .iter()
+            .map(|(name, provider)| (name.clone(), provider.clone_ref(py).into_any()))
+            .collect();
+        roots.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let name = match &slf.borrow().declarative_parent {
+            Some(cls) => cls.bind(py).qualname()?,
+            None => slf.get_type().qualname()?,
+        };
+        graph::Graph::build(py, name, roots)
+    }
+
+    /// Create named scope of `Scoped` providers.
+    ///
+    /// Scope is entered with `with` or `async with`.
+    fn scope(slf: &Bound<'_, Self>, name: String) -> scopes::Scope {
+        scopes::Scope::new(slf.py(), name, Some(slf.clone().into_any().unbind()))
+    }
+
+    /// Register hook called with `ResolutionEvent` of container resolutions.
+    ///
+    /// Hook observes container providers and providers resolved while they
+    /// are being resolved, e.g. their injections.
+    fn add_resolution_hook(slf: &Bound<'_, Self>, hook: PyObject) -> PyResult<()> {
+        tracing::add_container_hook(slf.as_any(), hook)
+    }
+
+    /// Unregister hook added with `add_resolution_hook()`.
+    fn remove_resolution_hook(slf: &Bound<'_, Self>, hook: PyObject) {
+        tracing::remove_container_hook(slf.py(), slf.as_any(), &hook);
+    }
+
+    /// Initialize all container resources.
+    ///
+    /// Returns an awaitable gathering async resources initialization, if any.
+    fn init_resources(&self, py: Python) -> PyResult<PyObject> {
+        let mut awaitables = Vec::new();
+        for resource in self.resources(py)? {
+            let result = resource.call_method0(py, intern!(py, "init"))?;
+            if awaitables::is_awaitable(result.bind(py)) {
+                awaitables.push(result);
+            }
+        }
+        if awaitables.is_empty() {
+            return Ok(py.None());
+        }
+        Ok(Bound::new(py, awaitables::gather(awaitables))?
+            .into_any()
+            .unbind())
+    }
+
+    /// Shutdown all container resources.
+    ///
+    /// Resources are shut down in dependency order: a resource is shut down
+    /// only after every initialized resource depending on it. Returns an
+    /// awaitable if any resource has async shutdown.
+    fn shutdown_resources(&self, py: Python) -> PyResult<PyObject> {
+        let resources = self.resources(py)?;
+        let Some(pending) = shutdown_resources_batches(py, &resources)? else {
+            return Ok(py.None());
+        };
+        let mut pending = Some(pending);
+        let awaitable =
+            awaitables::Awaitable::new(move |py: Python<'_>, value: PyResult<PyObject>| {
+                if let Some(pending) = pending.take() {
+                    return Ok(awaitables::Step::Await(pending));
+                }
+                value?;
+                match shutdown_resources_batches(py, &resources)? {
+                    Some(next) => Ok(awaitables::Step::Await(next)),
+                    None => Ok(awaitables::Step::Return(py.None())),
+                }
+            });
+        Ok(Bound::new(py, awaitable)?.into_any().unbind())
+    }
     //
     // pub fn load_config(&self) -> PyResult<()> {
     //     let providers = self.providers.clone();
@@ -489,42 +614,49 @@ impl DynamicContainer {
     //     SingletonResetContext { container: self }
     // }
     //
-    // fn check_dependencies(&mut self) -> PyResult<()> {
-    //     let undefined = self
-    //         .traverse(None)?
-    //         .call_method1("filter", (py().get("dependency_injector.providers")?.get("Dependency")?))?;
-    //     let undefined = undefined.call_method0("list")?;
-    //     let undefined = undefined
-    //         .iter()
-    //         .filter_map(|dependency| {
-    //             if dependency.is_instance_of(py().get("dependency_injector.providers")?.get("Dependency")?)? {
-    //                 Some(dependency.get("parent_name")?.as_str()?)
-    //             } else {
-    //                 None
-    //             }
-    //         })
-    //         .collect::<Vec<_>>();
-    //     if undefined.is_empty() {
-    //         return Ok(());
-    //     }
-    //     let container_name = if let Some(parent_name) = self.parent_name() {
-    //         parent_name
-    //     } else {
-    //         self.get_type().name().to_owned()
-    //     };
-    //     Err(py().new_err(format!(
-    //         "Container \"{}\" has undefined dependencies: {}",
-    //         container_name,
-    //         undefined.join(", ")
-    //     )))
-    // }
+    /// Check if container dependencies are defined.
+    ///
+    /// If any dependency is undefined, raises an error listing full paths
+    /// of undefined dependencies.
+    fn check_dependencies(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let dependency_type = providers::Dependency::type_object_bound(py).unbind();
+        let mut undefined = Vec::new();
+        let dependencies: Vec<_> = slf
+            .borrow()
+            .traverse(py, Some(vec![dependency_type]))?
+            .collect();
+        for dependency in dependencies {
+            let dependency = dependency?;
+            if !dependency
+                .getattr(intern!(py, "is_defined"))?
+                .extract::<bool>()?
+            {
+                undefined.push(providers::provider_label(&dependency)?);
+            }
+        }
+        if undefined.is_empty() {
+            return Ok(());
+        }
+        Err(errors::Error::new_err(format!(
+            "Container \"{}\" has undefined dependencies: {}",
+            Self::display_name(slf)?,
+            undefined.join(", ")
+        )))
+    }
 
     /// Build container providers from schema
-    fn from_schema(&mut self, py: Python, schema: Py<PyDict>) -> PyResult<()> {
+    fn from_schema(slf: &Bound<'_, Self>, schema: Py<PyDict>) -> PyResult<()> {
+        let py = slf.py();
         let schema = schema::build_schema(schema)?;
         let schema = schema.bind(py);
         for (name, provider) in schema.iter() {
-            self.set_provider(py, name.extract()?, provider.downcast()?)?;
+            if let Ok(self_provider) = provider.downcast::<providers::SelfProvider>() {
+                self_provider
+                    .borrow_mut()
+                    .set_container(slf.clone().into_any().unbind());
+            }
+            Self::set_provider(slf, name.extract()?, provider.downcast()?)?;
         }
         Ok(())
     }
@@ -537,51 +669,660 @@ impl DynamicContainer {
     //     self.from_schema(schema)
     // }
     //
-    fn from_json_schema(&mut self, py: Python, filepath: &str) -> PyResult<()> {
+    fn from_json_schema(slf: &Bound<'_, Self>, filepath: &str) -> PyResult<()> {
+        let py = slf.py();
         let payload = fs::read_to_string(filepath)?;
         let schema = py.import_bound("json")?.call_method1("loads", (payload,))?;
-        self.from_schema(py, schema.downcast()?.clone().into())?;
+        Self::from_schema(slf, schema.downcast()?.clone().into())?;
         Ok(())
     }
 
     /// Try to resolve provider name
-    fn resolve_provider_name(&self, provider: Bound<'_, providers::Provider>) -> PyResult<String> {
+    fn resolve_provider_name(&self, provider: &Bound<'_, PyAny>) -> PyResult<String> {
         for (provider_name, container_provider) in &self.providers {
-            if container_provider.is(provider.as_ref()) {
+            if container_provider.is(provider) {
                 return Ok(provider_name.to_owned());
             }
         }
-        Err(PyRuntimeError::new_err(format!(
+        Err(errors::Error::new_err(format!(
             "Can not resolve name for provider \"{}\"",
-            provider.get_type().name()?
+            provider.repr()?
         )))
     }
 
     /// Return parent name
     #[getter]
-    fn parent_name(&self, py: Python) -> PyResult<PyObject> {
+    fn parent_name(&self, py: Python) -> PyResult<Option<String>> {
         match (self.parent.as_ref(), self.declarative_parent.as_ref()) {
-            (Some(parent), _) => parent.call_method0(py, "parent_name"),
-            (_, Some(cls)) => Ok(cls.bind(py).name()?.into_py(py)),
-            _ => Ok(py.None()),
+            (Some(parent), _) => parent.getattr(py, intern!(py, "parent_name"))?.extract(py),
+            (_, Some(cls)) => cls.getattr(py, intern!(py, "__name__"))?.extract(py),
+            _ => Ok(None),
         }
     }
 
     /// Assign parent
-    fn assign_parent(&mut self, parent: Bound<'_, Self>) -> PyResult<()> {
-        self.parent = Some(parent.into());
+    fn assign_parent(&mut self, parent: PyObject) -> PyResult<()> {
+        self.parent = Some(parent);
         Ok(())
     }
 }
 
 impl DynamicContainer {
-    fn check_provider_type(&self, py: Python, provider: &Bound<'_, PyAny>) -> PyResult<()> {
-        if !provider.is_instance(&self.provider_type.bind(py)).is_ok() {
-            // let error_msg = format!("{} can contain only {} instances", self, self.provider_type);
-            let error_msg = "wrong provider type";
-            Err(PyRuntimeError::new_err(error_msg))
+    /// Return package relative module names are resolved against
+    fn resolve_package_name(&self, py: Python) -> PyResult<Option<String>> {
+        let sys = py.import_bound(intern!(py, "sys"))?;
+        let globals = match &self.declarative_parent {
+            Some(cls) => {
+                let module_name = cls.bind(py).getattr(intern!(py, "__module__"))?;
+                match sys
+                    .getattr(intern!(py, "modules"))?
+                    .call_method1(intern!(py, "get"), (module_name,))?
+                {
+                    module if module.is_none() => return Ok(None),
+                    module => module.getattr(intern!(py, "__dict__"))?,
+                }
+            }
+            None => sys
+                .call_method1(intern!(py, "_getframe"), (0,))?
+                .getattr(intern!(py, "f_globals"))?,
+        };
+        globals
+            .call_method1(intern!(py, "get"), ("__package__",))?
+            .extract()
+    }
+
+    /// Return all resource providers of the container, including nested ones.
+    fn resources(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let types = vec![providers::Resource::type_object_bound(py).unbind()];
+        self.traverse(py, Some(types))?
+            .map(|resource| resource.map(Bound::unbind))
+            .collect()
+    }
+
+    fn check_provider_type(slf: &Bound<'_, Self>, provider: &Bound<'_, PyAny>) -> PyResult<()> {
+        let provider_type = slf.borrow().provider_type.clone_ref(slf.py());
+        let provider_type = provider_type.bind(slf.py());
+        if !provider.is_instance(provider_type)? {
+            Err(errors::Error::new_err(format!(
+                "Container \"{}\" can contain only {} instances",
+                Self::display_name(slf)?,
+                provider_type.qualname()?
+            )))
         } else {
             Ok(())
         }
     }
+
+    /// Return container path for error messages, falling back to type name
+    pub(crate) fn display_name(slf: &Bound<'_, Self>) -> PyResult<String> {
+        match slf.borrow().parent_name(slf.py())? {
+            Some(parent_name) => Ok(parent_name),
+            None => slf.get_type().qualname(),
+        }
+    }
+}
+
+/// Return container path for messages, declarative containers are named
+/// by their class.
+pub(crate) fn container_label(container: &Bound<'_, PyAny>) -> PyResult<String> {
+    let py = container.py();
+    if let Ok(container) = container.downcast::<DynamicContainer>() {
+        return DynamicContainer::display_name(container);
+    }
+    if let Ok(parent_name) = container.getattr(intern!(py, "parent_name")) {
+        if let Ok(Some(parent_name)) = parent_name.extract::<Option<String>>() {
+            return Ok(parent_name);
+        }
+    }
+    match container.downcast::<PyType>() {
+        Ok(cls) => cls.qualname(),
+        Err(_) => container.get_type().qualname(),
+    }
+}
+
+static DECLARATIVE_CONTAINER_METACLASS: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+
+// Python can not subclass `type` from Rust, so metaclass body is a thin
+// shell delegating to the functions below.
+const DECLARATIVE_CONTAINER_SOURCE: &str = r#"
+class DeclarativeContainerMetaClass(type):
+    """Declarative inversion of control container meta class."""
+
+    __module__ = "inj"
+
+    def __new__(mcs, class_name, bases, attributes):
+        return _metaclass_new(mcs, class_name, bases, attributes)
+
+    def __setattr__(cls, name, value):
+        _metaclass_setattr(cls, name, value)
+
+    def __delattr__(cls, name):
+        _metaclass_delattr(cls, name)
+
+    @property
+    def dependencies(cls):
+        return _metaclass_dependencies(cls)
+
+    @property
+    def parent_name(cls):
+        return _metaclass_parent_name(cls)
+
+    def traverse(cls, types=None):
+        return _metaclass_traverse(cls, types)
+
+    def resolve_provider_name(cls, provider):
+        return _metaclass_resolve_provider_name(cls, provider)
+
+    def assign_parent(cls, parent):
+        type.__setattr__(cls, "parent", parent)
+
+
+class DeclarativeContainer(metaclass=DeclarativeContainerMetaClass):
+    """Declarative inversion of control container."""
+
+    __module__ = "inj"
+
+    def __new__(cls, **overriding_providers):
+        return _declarative_container_new(cls, overriding_providers)
+"#;
+
+/// Register `DeclarativeContainerMetaClass` and `DeclarativeContainer`
+pub fn add_declarative_container(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    let globals = PyDict::new_bound(py);
+    globals.set_item("_metaclass_new", wrap_pyfunction_bound!(metaclass_new, m)?)?;
+    globals.set_item(
+        "_metaclass_setattr",
+        wrap_pyfunction_bound!(metaclass_setattr, m)?,
+    )?;
+    globals.set_item(
+        "_metaclass_delattr",
+        wrap_pyfunction_bound!(metaclass_delattr, m)?,
+    )?;
+    globals.set_item(
+        "_metaclass_dependencies",
+        wrap_pyfunction_bound!(metaclass_dependencies, m)?,
+    )?;
+    globals.set_item(
+        "_metaclass_parent_name",
+        wrap_pyfunction_bound!(metaclass_parent_name, m)?,
+    )?;
+    globals.set_item(
+        "_metaclass_traverse",
+        wrap_pyfunction_bound!(metaclass_traverse, m)?,
+    )?;
+    globals.set_item(
+        "_metaclass_resolve_provider_name",
+        wrap_pyfunction_bound!(metaclass_resolve_provider_name, m)?,
+    )?;
+    globals.set_item(
+        "_declarative_container_new",
+        wrap_pyfunction_bound!(declarative_container_new, m)?,
+    )?;
+    py.run_bound(DECLARATIVE_CONTAINER_SOURCE, Some(&globals), None)?;
+
+    let metaclass = globals
+        .get_item("DeclarativeContainerMetaClass")?
+        .ok_or_else(|| PyRuntimeError::new_err("DeclarativeContainerMetaClass is not defined"))?;
+    let metaclass = metaclass.downcast_into::<PyType>()?;
+    let _ = DECLARATIVE_CONTAINER_METACLASS.set(py, metaclass.clone().unbind());
+
+    let declarative_container = globals
+        .get_item("DeclarativeContainer")?
+        .ok_or_else(|| PyRuntimeError::new_err("DeclarativeContainer is not defined"))?;
+    let type_setattr = py
+        .get_type_bound::<PyType>()
+        .getattr(intern!(py, "__setattr__"))?;
+    type_setattr.call1((
+        &declarative_container,
+        "provider_type",
+        providers::Provider::type_object_bound(py),
+    ))?;
+    type_setattr.call1((
+        &declarative_container,
+        "wiring_config",
+        WiringConfiguration::default().into_py(py),
+    ))?;
+    type_setattr.call1((&declarative_container, "parent", py.None()))?;
+
+    m.add("DeclarativeContainerMetaClass", metaclass)?;
+    m.add("DeclarativeContainer", declarative_container)?;
+    Ok(())
+}
+
+/// Find dependency cycle reachable from the provider, depth first.
+///
+/// Returns providers of the cycle, the first one repeated at the end.
+fn find_cycle<'py>(
+    provider: &Bound<'py, PyAny>,
+    path: &mut Vec<Bound<'py, PyAny>>,
+    done: &mut HashSet<usize>,
+) -> PyResult<Option<Vec<Bound<'py, PyAny>>>> {
+    let key = provider.as_ptr() as usize;
+    if done.contains(&key) {
+        return Ok(None);
+    }
+    if let Some(position) = path.iter().position(|visited| visited.is(provider)) {
+        let mut cycle = path[position..].to_vec();
+        cycle.push(provider.clone());
+        return Ok(Some(cycle));
+    }
+
+    path.push(provider.clone());
+    if !provider.is_instance_of::<providers::Delegate>() {
+        for related in provider
+            .getattr(intern!(provider.py(), "related"))?
+            .iter()?
+        {
+            if let Some(cycle) = find_cycle(&related?, path, done)? {
+                return Ok(Some(cycle));
+            }
+        }
+    }
+    path.pop();
+    done.insert(key);
+    Ok(None)
+}
+
+/// Shutdown initialized resources batch by batch.
+///
+/// Stops at the first batch with async shutdowns, returning the awaitable
+/// gathering them. The rest is shut down by calling it again afterwards.
+fn shutdown_resources_batches(py: Python, resources: &[PyObject]) -> PyResult<Option<PyObject>> {
+    loop {
+        let mut initialized = Vec::new();
+        for resource in resources {
+            if resource
+                .getattr(py, intern!(py, "initialized"))?
+                .is_truthy(py)?
+            {
+                initialized.push(resource.bind(py));
+            }
+        }
+        if initialized.is_empty() {
+            return Ok(None);
+        }
+
+        let mut independent = Vec::new();
+        for resource in &initialized {
+            let mut is_required = false;
+            for other in &initialized {
+                if other.is(*resource) {
+                    continue;
+                }
+                let related = other.getattr(intern!(py, "related"))?;
+                if related.contains(resource)? {
+                    is_required = true;
+                    break;
+                }
+            }
+            if !is_required {
+                independent.push(*resource);
+            }
+        }
+        if independent.is_empty() {
+            return Err(PyRuntimeError::new_err(
+                "Unable to resolve resources shutdown order",
+            ));
+        }
+
+        let mut awaitables = Vec::new();
+        for resource in independent {
+            let result = resource.call_method0(intern!(py, "shutdown"))?;
+            if awaitables::is_awaitable(&result) {
+                awaitables.push(result.unbind());
+            }
+        }
+        if !awaitables.is_empty() {
+            let gather = awaitables::gather(awaitables);
+            return Ok(Some(Bound::new(py, gather)?.into_any().unbind()));
+        }
+    }
+}
+
+/// Return module object, importing it if module name is given
+fn resolve_module<'py>(
+    module: &Bound<'py, PyAny>,
+    from_package: Option<&str>,
+) -> PyResult<Bound<'py, PyModule>> {
+    match module.downcast::<PyModule>() {
+        Ok(module) => Ok(module.clone()),
+        Err(_) => wiring::import_module(module.py(), module.extract()?, from_package),
+    }
+}
+
+/// Check if object is a declarative container class
+pub fn is_declarative_container(obj: &Bound<'_, PyAny>) -> PyResult<bool> {
+    match DECLARATIVE_CONTAINER_METACLASS.get(obj.py()) {
+        Some(metaclass) => obj.is_instance(metaclass.bind(obj.py())),
+        None => Ok(false),
+    }
+}
+
+/// Check if object is a container, either declarative class or dynamic instance
+pub fn is_container(obj: &Bound<'_, PyAny>) -> PyResult<bool> {
+    Ok(obj.is_instance_of::<DynamicContainer>() || is_declarative_container(obj)?)
+}
+
+fn check_declarative_provider_type(
+    cls: &Bound<'_, PyType>,
+    provider: &Bound<'_, PyAny>,
+) -> PyResult<()> {
+    let py = cls.py();
+    let provider_type = cls.getattr(intern!(py, "provider_type"))?;
+    if !provider.is_instance(&provider_type)? {
+        return Err(errors::Error::new_err(format!(
+            "Container \"{}\" can contain only {} instances",
+            cls.qualname()?,
+            provider_type.getattr(intern!(py, "__qualname__"))?
+        )));
+    }
+    Ok(())
+}
+
+#[pyfunction]
+fn metaclass_new<'py>(
+    mcs: &Bound<'py, PyType>,
+    class_name: &str,
+    bases: &Bound<'py, PyTuple>,
+    attributes: &Bound<'py, PyDict>,
+) -> PyResult<Bound<'py, PyType>> {
+    let py = mcs.py();
+
+    let containers = PyDict::new_bound(py);
+    let cls_providers = PyDict::new_bound(py);
+    let mut self_provider: Option<Bound<'py, providers::SelfProvider>> = None;
+    let mut self_alt_names = Vec::new();
+    for (name, value) in attributes.iter() {
+        if is_container(&value)? {
+            containers.set_item(&name, &value)?;
+        }
+        if let Ok(provider) = value.downcast::<providers::SelfProvider>() {
+            if self_provider
+                .as_ref()
+                .is_some_and(|found| !found.is(provider))
+            {
+                return Err(errors::Error::new_err(format!(
+                    "Container \"{class_name}\" can have only one \"Self\" provider"
+                )));
+            }
+            if name.to_string() != "__self__" {
+                self_alt_names.push(name.to_string());
+            }
+            self_provider = Some(provider.clone());
+        } else if value.is_instance_of::<providers::Provider>() {
+            providers::Provider::set_name(&value, &name.extract::<String>()?);
+            cls_providers.set_item(&name, &value)?;
+        }
+    }
+    let self_provider = match self_provider {
+        Some(provider) => provider,
+        None => Bound::new(py, providers::SelfProvider::initializer())?,
+    };
+    self_provider.borrow_mut().set_alt_names(self_alt_names);
+    attributes.set_item("__self__", &self_provider)?;
+
+    let inherited_providers = PyDict::new_bound(py);
+    for base in bases.iter() {
+        if is_declarative_container(&base)? {
+            inherited_providers.update(
+                base.getattr(intern!(py, "providers"))?
+                    .downcast::<PyDict>()?
+                    .as_mapping(),
+            )?;
+        }
+    }
+
+    let all_providers = inherited_providers.copy()?;
+    all_providers.update(cls_providers.as_mapping())?;
+
+    attributes.set_item("containers", containers)?;
+    attributes.set_item("inherited_providers", inherited_providers)?;
+    attributes.set_item("cls_providers", &cls_providers)?;
+    attributes.set_item("providers", &all_providers)?;
+
+    let cls = py
+        .get_type_bound::<PyType>()
+        .call_method1(intern!(py, "__new__"), (mcs, class_name, bases, attributes))?
+        .downcast_into::<PyType>()?;
+
+    if cls.hasattr(intern!(py, "provider_type"))? {
+        for provider in all_providers.values() {
+            check_declarative_provider_type(&cls, &provider)?;
+        }
+    }
+    for provider in cls_providers.values() {
+        providers::Provider::set_owner(&provider, cls.as_any())?;
+        if providers::is_child_provider(&provider)? {
+            provider.call_method1(intern!(py, "assign_parent"), (&cls,))?;
+        }
+    }
+    self_provider
+        .borrow_mut()
+        .set_container(cls.clone().into_any().unbind());
+    Ok(cls)
+}
+
+#[pyfunction]
+fn metaclass_setattr(
+    cls: &Bound<'_, PyType>,
+    name: &str,
+    value: &Bound<'_, PyAny>,
+) -> PyResult<()> {
+    let py = cls.py();
+    if value.is_instance_of::<providers::Provider>()
+        && !value.is_instance_of::<providers::SelfProvider>()
+        && name != "parent"
+    {
+        check_declarative_provider_type(cls, value)?;
+        providers::Provider::set_name(value, name);
+        providers::Provider::set_owner(value, cls.as_any())?;
+        if providers::is_child_provider(value)? {
+            value.call_method1(intern!(py, "assign_parent"), (cls,))?;
+        }
+        cls.getattr(intern!(py, "providers"))?
+            .set_item(name, value)?;
+        cls.getattr(intern!(py, "cls_providers"))?
+            .set_item(name, value)?;
+    }
+    py.get_type_bound::<PyType>()
+        .getattr(intern!(py, "__setattr__"))?
+        .call1((cls, name, value))?;
+    Ok(())
+}
+
+#[pyfunction]
+fn metaclass_delattr(cls: &Bound<'_, PyType>, name: &str) -> PyResult<()> {
+    let py = cls.py();
+    let providers = cls.getattr(intern!(py, "providers"))?;
+    let cls_providers = cls.getattr(intern!(py, "cls_providers"))?;
+    if providers.contains(name)? && cls_providers.contains(name)? {
+        providers.del_item(name)?;
+        cls_providers.del_item(name)?;
+    }
+    py.get_type_bound::<PyType>()
+        .getattr(intern!(py, "__delattr__"))?
+        .call1((cls, name))?;
+    Ok(())
+}
+
+#[pyfunction]
+fn metaclass_dependencies<'py>(cls: &Bound<'py, PyType>) -> PyResult<Bound<'py, PyDict>> {
+    let py = cls.py();
+    let dependencies = PyDict::new_bound(py);
+    let providers = cls.getattr(intern!(py, "providers"))?;
+    for (name, provider) in providers.downcast::<PyDict>()?.iter() {
+        if provider.is_instance_of::<providers::Dependency>()
+            || provider.is_instance_of::<providers::DependenciesContainer>()
+        {
+            dependencies.set_item(name, provider)?;
+        }
+    }
+    Ok(dependencies)
+}
+
+#[pyfunction]
+fn metaclass_parent_name(cls: &Bound<'_, PyType>) -> PyResult<Option<String>> {
+    let py = cls.py();
+    let parent = cls.getattr(intern!(py, "parent"))?;
+    if parent.is_none() {
+        Ok(Some(
+            cls.getattr(intern!(py, "__name__"))?.extract::<String>()?,
+        ))
+    } else {
+        parent.getattr(intern!(py, "parent_name"))?.extract()
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (cls, types=None))]
+fn metaclass_traverse<'py>(
+    cls: &Bound<'py, PyType>,
+    types: Option<Vec<Py<PyType>>>,
+) -> PyResult<Bound<'py, PyIterator>> {
+    let py = cls.py();
+    let providers = cls
+        .getattr(intern!(py, "providers"))?
+        .downcast::<PyDict>()?
+        .values()
+        .extract()?;
+    providers::traverse(py, providers, types)
+}
+
+#[pyfunction]
+fn metaclass_resolve_provider_name(
+    cls: &Bound<'_, PyType>,
+    provider: &Bound<'_, PyAny>,
+) -> PyResult<String> {
+    let py = cls.py();
+    let providers = cls.getattr(intern!(py, "providers"))?;
+    for (name, container_provider) in providers.downcast::<PyDict>()?.iter() {
+        if container_provider.is(provider) {
+            return name.extract();
+        }
+    }
+    Err(errors::Error::new_err(format!(
+        "Can not resolve name for provider \"{}\"",
+        provider.repr()?
+    )))
+}
+
+/// Instantiate declarative container, producing a dynamic container with
+/// deep-copied providers of the declarative one.
+#[pyfunction]
+fn declarative_container_new<'py>(
+    cls: &Bound<'py, PyType>,
+    overriding_providers: &Bound<'py, PyDict>,
+) -> PyResult<Bound<'py, DynamicContainer>> {
+    let py = cls.py();
+    let container = Bound::new(py, DynamicContainer::new(py))?;
+    {
+        let mut this = container.borrow_mut();
+        this.provider_type = cls
+            .getattr(intern!(py, "provider_type"))?
+            .downcast_into::<PyType>()?
+            .unbind();
+        this.wiring_config = cls.getattr(intern!(py, "wiring_config"))?.extract()?;
+        this.declarative_parent = Some(cls.clone().unbind());
+    }
+
+    // Providers and `Self` share the memo, so providers injecting `Self`
+    // get the copy bound to the new container.
+    let memo = PyDict::new_bound(py);
+    let copied_providers = providers::deepcopy(&cls.getattr(intern!(py, "providers"))?, &memo)?;
+    for (name, provider) in copied_providers.downcast::<PyDict>()?.iter() {
+        DynamicContainer::set_provider(&container, name.extract()?, provider.downcast()?)?;
+    }
+    let self_provider = providers::deepcopy(&cls.getattr(intern!(py, "__self__"))?, &memo)?;
+    let alt_names = {
+        let mut copied = self_provider
+            .downcast::<providers::SelfProvider>()?
+            .borrow_mut();
+        copied.set_container(container.clone().into_any().unbind());
+        copied.alt_names()
+    };
+    DynamicContainer::__setattr__(&container, "__self__".to_owned(), &self_provider)?;
+    for name in alt_names {
+        DynamicContainer::__setattr__(&container, name, &self_provider)?;
+    }
+
+    for (name, overriding_provider) in overriding_providers.iter() {
+        container
+            .getattr(name.downcast::<PyString>()?)?
+            .call_method1(intern!(py, "override"), (overriding_provider,))?;
+    }
+
+    if container.borrow().wiring_config.auto_wire {
+        DynamicContainer::wire(&container, None, None, None)?;
+        wiring::register_loader_container(container.as_any())?;
+    }
+    Ok(container)
+}
+
+/// Create a decorator copying providers of base declarative container.
+///
+/// Providers redefined in decorated container replace providers of the same
+/// name everywhere in the copied provider graph.
+#[pyfunction]
+pub fn copy<'py>(base_container: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyCFunction>> {
+    let py = base_container.py();
+    if !is_declarative_container(base_container)? {
+        return Err(errors::Error::new_err(format!(
+            "Container copy source must be a declarative container, got {}",
+            base_container.repr()?
+        )));
+    }
+    let base_container = base_container.clone().unbind();
+    PyCFunction::new_closure_bound(
+        py,
+        None,
+        None,
+        move |args: &Bound<'_, PyTuple>,
+              _kwargs: Option<&Bound<'_, PyDict>>|
+              -> PyResult<PyObject> {
+            let py = args.py();
+            let copied_container = args.get_item(0)?;
+            let base_providers = base_container.getattr(py, intern!(py, "providers"))?;
+
+            let memo = PyDict::new_bound(py);
+            memo_matching_names(
+                copied_container
+                    .getattr(intern!(py, "cls_providers"))?
+                    .downcast()?,
+                base_providers.downcast_bound(py)?,
+                &memo,
+            )?;
+
+            let providers_copy = providers::deepcopy(base_providers.bind(py), &memo)?;
+            for (name, provider) in providers_copy.downcast::<PyDict>()?.iter() {
+                copied_container.setattr(name.downcast::<PyString>()?, provider)?;
+            }
+            Ok(copied_container.unbind())
+        },
+    )
+}
+
+/// Map base providers to same-named new providers, including nested containers
+fn memo_matching_names(
+    new_providers: &Bound<'_, PyDict>,
+    base_providers: &Bound<'_, PyDict>,
+    memo: &Bound<'_, PyDict>,
+) -> PyResult<()> {
+    let py = memo.py();
+    for (name, new_provider) in new_providers.iter() {
+        let Some(source_provider) = base_providers.get_item(&name)? else {
+            continue;
+        };
+        providers::memo_set(&source_provider, &new_provider, memo)?;
+
+        let providers_attr = intern!(py, "providers");
+        if new_provider.hasattr(providers_attr)? && source_provider.hasattr(providers_attr)? {
+            memo_matching_names(
+                new_provider.getattr(providers_attr)?.downcast()?,
+                source_provider.getattr(providers_attr)?.downcast()?,
+                memo,
+            )?;
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,644 @@
+//! Dependency injection without Python.
+//!
+//! Same wiring model as the Python providers: a container of named
+//! providers, factories creating an instance per call, singletons and
+//! providers scoped to named scopes, overriding and cycle detection.
+//!
+//! Python providers do not resolve through these types yet, binding them
+//! on top of this module is a follow-up of its own. Until then they share
+//! `Error`, so messages of cycles and missing scopes are the same.
+//!
+//! ```rust
+//! use inj::core::{Container, Factory, Singleton};
+//!
+//! struct Database {
+//!     url: String,
+//! }
+//!
+//! struct Service {
+//!     database: std::sync::Arc<Database>,
+//! }
+//!
+//! let mut container = Container::new();
+//! let database = container.register(
+//!     "database",
+//!     Singleton::new(|_| Ok(Database { url: "sqlite://".into() })),
+//! );
+//! container.register(
+//!     "service",
+//!     Factory::new(|r| Ok(Service { database: r.get("database")? })),
+//! );
+//!
+//! let service = container.get::<Service>("service").unwrap();
+//! assert_eq!(service.database.url, "sqlite://");
+//!
+//! database.reset();
+//! let service = container.get::<Service>("service").unwrap();
+//! assert_eq!(service.database.url, "sqlite://");
+//! ```
+
+use std::any::{type_name, Any};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+/// Provided instance
+pub type Instance = Arc<dyn Any + Send + Sync>;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Failure of the provider resolution
+#[derive(Debug)]
+pub enum Error {
+    /// No provider is registered with the name
+    NotFound(String),
+    /// Provided instance is not of the requested type
+    TypeMismatch {
+        name: String,
+        expected: &'static str,
+    },
+    /// Providers depend on each other, first and last names are the same
+    CircularDependency(Vec<String>),
+    /// Scoped provider is resolved outside of its scope
+    ScopeNotEntered { name: String, scope: String },
+    /// Error returned by the provided function
+    Other(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl Error {
+    /// Wrap error of the provided function.
+    pub fn other(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::Other(error.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(name) => write!(f, "Provider \"{name}\" is not registered"),
+            Self::TypeMismatch { name, expected } => {
+                write!(f, "Provider \"{name}\" does not provide {expected}")
+            }
+            Self::CircularDependency(cycle) => {
+                write!(f, "Circular dependency: {}", cycle.join(" -> "))
+            }
+            Self::ScopeNotEntered { name, scope } => write!(
+                f,
+                "Provider \"{name}\" requires scope \"{scope}\" to be entered"
+            ),
+            Self::Other(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Other(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Provider of instances registered in a container.
+pub trait Provider: Send + Sync {
+    /// Return provided instance, resolving dependencies with the resolver.
+    fn provide(&self, resolver: &Resolver<'_>) -> Result<Instance>;
+}
+
+/// Function creating an instance with resolved dependencies
+type Create<T> = Box<dyn Fn(&Resolver<'_>) -> Result<T> + Send + Sync>;
+
+/// Factory provider creates a new instance on every resolution.
+pub struct Factory<T> {
+    create: Create<T>,
+}
+
+impl<T: Any + Send + Sync> Factory<T> {
+    pub fn new(create: impl Fn(&Resolver<'_>) -> Result<T> + Send + Sync + 'static) -> Self {
+        Self {
+            create: Box::new(create),
+        }
+    }
+}
+
+impl<T: Any + Send + Sync> Provider for Factory<T> {
+    fn provide(&self, resolver: &Resolver<'_>) -> Result<Instance> {
+        Ok(Arc::new((self.create)(resolver)?))
+    }
+}
+
+/// Singleton provider creates an instance on the first resolution and
+/// returns the same instance afterwards.
+///
+/// Concurrent resolutions wait for the instance being created. Cycles are
+/// detected per resolution, so singletons depending on each other and
+/// resolved from two threads at once deadlock instead of failing with
+/// `Error::CircularDependency`.
+pub struct Singleton<T> {
+    create: Create<T>,
+    instance: Mutex<Option<Instance>>,
+}
+
+impl<T: Any + Send + Sync> Singleton<T> {
+    pub fn new(create: impl Fn(&Resolver<'_>) -> Result<T> + Send + Sync + 'static) -> Self {
+        Self {
+            create: Box::new(create),
+            instance: Mutex::new(None),
+        }
+    }
+
+    /// Drop the instance, next resolution creates a new one.
+    pub fn reset(&self) {
+        *self.instance.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+impl<T: Any + Send + Sync> Provider for Singleton<T> {
+    fn provide(&self, resolver: &Resolver<'_>) -> Result<Instance> {
+        // Lock is held while creating, so concurrent resolutions wait for
+        // the instance instead of creating another one
+        let mut instance = self.instance.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(instance) = &*instance {
+            return Ok(instance.clone());
+        }
+        let created: Instance = Arc::new((self.create)(resolver)?);
+        *instance = Some(created.clone());
+        Ok(created)
+    }
+}
+
+/// Scoped provider creates an instance once per entered scope with the name.
+///
+/// Resolution fails outside of the scope, see `Container::enter_scope()`.
+pub struct Scoped<T> {
+    scope: String,
+    create: Create<T>,
+}
+
+impl<T: Any + Send + Sync> Scoped<T> {
+    pub fn new(
+        scope: impl Into<String>,
+        create: impl Fn(&Resolver<'_>) -> Result<T> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            scope: scope.into(),
+            create: Box::new(create),
+        }
+    }
+}
+
+impl<T: Any + Send + Sync> Provider for Scoped<T> {
+    fn provide(&self, resolver: &Resolver<'_>) -> Result<Instance> {
+        let Some(scope) = resolver.scope.and_then(|scope| scope.find(&self.scope)) else {
+            return Err(Error::ScopeNotEntered {
+                name: resolver.current().unwrap_or_default(),
+                scope: self.scope.clone(),
+            });
+        };
+        let key = self as *const Self as usize;
+        if let Some(instance) = scope.instance(key) {
+            return Ok(instance);
+        }
+        let created: Instance = Arc::new((self.create)(resolver)?);
+        Ok(scope.store(key, created))
+    }
+}
+
+/// Object provider returns the same given instance.
+pub struct Object {
+    instance: Instance,
+}
+
+impl Object {
+    pub fn new<T: Any + Send + Sync>(instance: T) -> Self {
+        Self {
+            instance: Arc::new(instance),
+        }
+    }
+}
+
+impl Provider for Object {
+    fn provide(&self, _resolver: &Resolver<'_>) -> Result<Instance> {
+        Ok(self.instance.clone())
+    }
+}
+
+/// Container of named providers.
+///
+/// Providers are overridden through a shared reference, so overriding
+/// works while the container is used, e.g. in tests.
+#[derive(Default)]
+pub struct Container {
+    providers: HashMap<String, Arc<dyn Provider>>,
+    overridden: RwLock<HashMap<String, Vec<Arc<dyn Provider>>>>,
+}
+
+impl Container {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register provider with the name, replacing previous one.
+    ///
+    /// Returns the registered provider, e.g. to reset a `Singleton`.
+    pub fn register<P: Provider + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        provider: P,
+    ) -> Arc<P> {
+        let provider = Arc::new(provider);
+        self.register_shared(name, provider.clone());
+        provider
+    }
+
+    /// Register provider shared with other containers, replacing previous one.
+    pub fn register_shared(&mut self, name: impl Into<String>, provider: Arc<dyn Provider>) {
+        self.providers.insert(name.into(), provider);
+    }
+
+    /// Return names of registered providers.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.providers.keys().map(String::as_str)
+    }
+
+    /// Check if provider is registered with the name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.providers.contains_key(name)
+    }
+
+    /// Override the provider, last overriding provider takes precedence.
+    pub fn override_provider(&self, name: &str, provider: impl Provider + 'static) -> Result<()> {
+        if !self.contains(name) {
+            return Err(Error::NotFound(name.to_owned()));
+        }
+        self.overridden
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(name.to_owned())
+            .or_default()
+            .push(Arc::new(provider));
+        Ok(())
+    }
+
+    /// Remove the last overriding provider.
+    pub fn reset_last_overriding(&self, name: &str) {
+        let mut overridden = self
+            .overridden
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(providers) = overridden.get_mut(name) {
+            providers.pop();
+            if providers.is_empty() {
+                overridden.remove(name);
+            }
+        }
+    }
+
+    /// Remove all overriding providers.
+    pub fn reset_override(&self) {
+        self.overridden
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// Return provided instance.
+    pub fn resolve(&self, name: &str) -> Result<Instance> {
+        Resolver::new(self, None).resolve(name)
+    }
+
+    /// Return provided instance of the type.
+    pub fn get<T: Any + Send + Sync>(&self, name: &str) -> Result<Arc<T>> {
+        Resolver::new(self, None).get(name)
+    }
+
+    /// Enter named scope of `Scoped` providers, instances are dropped with
+    /// the scope.
+    pub fn enter_scope(&self, name: impl Into<String>) -> Scope<'_> {
+        Scope::new(self, None, name.into())
+    }
+
+    /// Return the provider resolved by the name
+    fn provider(&self, name: &str) -> Option<Arc<dyn Provider>> {
+        let overridden = self
+            .overridden
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        match overridden.get(name).and_then(|providers| providers.last()) {
+            Some(provider) => Some(provider.clone()),
+            None => self.providers.get(name).cloned(),
+        }
+    }
+}
+
+/// Entered named scope, holding instances of `Scoped` providers.
+///
+/// Nested scopes resolve providers of enclosing scopes too.
+pub struct Scope<'c> {
+    container: &'c Container,
+    parent: Option<&'c Scope<'c>>,
+    name: String,
+    instances: Mutex<HashMap<usize, Instance>>,
+}
+
+impl<'c> Scope<'c> {
+    fn new(container: &'c Container, parent: Option<&'c Scope<'c>>, name: String) -> Self {
+        Self {
+            container,
+            parent,
+            name,
+            instances: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Return scope name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Enter nested scope.
+    pub fn enter_scope(&self, name: impl Into<String>) -> Scope<'_> {
+        Scope::new(self.container, Some(self), name.into())
+    }
+
+    /// Return provided instance.
+    pub fn resolve(&self, name: &str) -> Result<Instance> {
+        Resolver::new(self.container, Some(self)).resolve(name)
+    }
+
+    /// Return provided instance of the type.
+    pub fn get<T: Any + Send + Sync>(&self, name: &str) -> Result<Arc<T>> {
+        Resolver::new(self.container, Some(self)).get(name)
+    }
+
+    /// Return the innermost scope with the name
+    fn find(&self, name: &str) -> Option<&Scope<'c>> {
+        let mut scope = Some(self);
+        while let Some(current) = scope {
+            if current.name == name {
+                return Some(current);
+            }
+            scope = current.parent;
+        }
+        None
+    }
+
+    fn instance(&self, key: usize) -> Option<Instance> {
+        self.instances
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+            .cloned()
+    }
+
+    /// Cache instance, returning the one cached first
+    fn store(&self, key: usize, instance: Instance) -> Instance {
+        self.instances
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(key)
+            .or_insert(instance)
+            .clone()
+    }
+}
+
+/// Resolution of a top-level call, passed to providers to resolve their
+/// dependencies.
+pub struct Resolver<'a> {
+    container: &'a Container,
+    scope: Option<&'a Scope<'a>>,
+    /// Names and providers being resolved, innermost last
+    resolving: RefCell<Vec<(String, usize)>>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(container: &'a Container, scope: Option<&'a Scope<'a>>) -> Self {
+        Self {
+            container,
+            scope,
+            resolving: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Return provided instance.
+    pub fn resolve(&self, name: &str) -> Result<Instance> {
+        let provider = self
+            .container
+            .provider(name)
+            .ok_or_else(|| Error::NotFound(name.to_owned()))?;
+        // Provider registered under several names is the same provider
+        let identity = Arc::as_ptr(&provider) as *const () as usize;
+        {
+            let mut resolving = self.resolving.borrow_mut();
+            if let Some(position) = resolving
+                .iter()
+                .position(|(resolving, provider)| resolving == name || *provider == identity)
+            {
+                let mut cycle: Vec<String> = resolving[position..]
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .collect();
+                cycle.push(name.to_owned());
+                return Err(Error::CircularDependency(cycle));
+            }
+            resolving.push((name.to_owned(), identity));
+        }
+        let result = provider.provide(self);
+        self.resolving.borrow_mut().pop();
+        result
+    }
+
+    /// Return provided instance of the type.
+    pub fn get<T: Any + Send + Sync>(&self, name: &str) -> Result<Arc<T>> {
+        self.resolve(name)?
+            .downcast::<T>()
+            .map_err(|_| Error::TypeMismatch {
+                name: name.to_owned(),
+                expected: type_name::<T>(),
+            })
+    }
+
+    /// Return name of the provider being resolved
+    fn current(&self) -> Option<String> {
+        self.resolving.borrow().last().map(|(name, _)| name.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Barrier;
+    use std::thread;
+
+    fn value(container: &Container, name: &str) -> i32 {
+        *container.get::<i32>(name).unwrap()
+    }
+
+    #[test]
+    fn overriding_takes_precedence_until_reset() {
+        let mut container = Container::new();
+        container.register("value", Object::new(1));
+        container
+            .override_provider("value", Object::new(2))
+            .unwrap();
+        container
+            .override_provider("value", Object::new(3))
+            .unwrap();
+        assert_eq!(value(&container, "value"), 3);
+        container.reset_last_overriding("value");
+        assert_eq!(value(&container, "value"), 2);
+        container.reset_last_overriding("value");
+        assert_eq!(value(&container, "value"), 1);
+        container.reset_last_overriding("value");
+        assert_eq!(value(&container, "value"), 1);
+    }
+
+    #[test]
+    fn overriding_requires_registered_provider() {
+        let container = Container::new();
+        let error = container
+            .override_provider("missing", Object::new(1))
+            .unwrap_err();
+        assert!(matches!(error, Error::NotFound(name) if name == "missing"));
+    }
+
+    #[test]
+    fn dependencies_see_overriding() {
+        let mut container = Container::new();
+        container.register("value", Object::new(1));
+        container.register("double", Factory::new(|r| Ok(*r.get::<i32>("value")? * 2)));
+        container
+            .override_provider("value", Object::new(5))
+            .unwrap();
+        assert_eq!(value(&container, "double"), 10);
+        container.reset_override();
+        assert_eq!(value(&container, "double"), 2);
+    }
+
+    #[test]
+    fn cycle_reports_path() {
+        let mut container = Container::new();
+        container.register("a", Factory::new(|r| r.get::<i32>("b").map(|b| *b)));
+        container.register("b", Factory::new(|r| r.get::<i32>("c").map(|c| *c)));
+        container.register("c", Factory::new(|r| r.get::<i32>("b").map(|b| *b)));
+        let error = container.resolve("a").unwrap_err();
+        assert!(matches!(&error, Error::CircularDependency(path) if path == &["b", "c", "b"]));
+        assert_eq!(error.to_string(), "Circular dependency: b -> c -> b");
+    }
+
+    #[test]
+    fn cycle_through_provider_shared_by_names() {
+        let mut container = Container::new();
+        let shared: Arc<dyn Provider> = Arc::new(Singleton::new(|r| {
+            r.get::<i32>("alias").map(|alias| *alias)
+        }));
+        container.register_shared("value", shared.clone());
+        container.register_shared("alias", shared);
+        let error = container.resolve("value").unwrap_err();
+        assert!(matches!(&error, Error::CircularDependency(path) if path == &["value", "alias"]));
+    }
+
+    #[test]
+    fn shared_dependency_is_not_a_cycle() {
+        let mut container = Container::new();
+        container.register("value", Object::new(1));
+        container.register(
+            "sum",
+            Factory::new(|r| Ok(*r.get::<i32>("value")? + *r.get::<i32>("value")?)),
+        );
+        assert_eq!(value(&container, "sum"), 2);
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let mut container = Container::new();
+        container.register("value", Object::new(1));
+        let error = container.get::<String>("value").unwrap_err();
+        assert!(matches!(
+            error,
+            Error::TypeMismatch { name, expected }
+                if name == "value" && expected == type_name::<String>()
+        ));
+    }
+
+    #[test]
+    fn scope_must_be_entered() {
+        let mut container = Container::new();
+        container.register("request", Scoped::new("request", |_| Ok(1)));
+        let error = container.resolve("request").unwrap_err();
+        assert!(matches!(
+            &error,
+            Error::ScopeNotEntered { name, scope } if name == "request" && scope == "request"
+        ));
+        let session = container.enter_scope("session");
+        assert!(matches!(
+            session.resolve("request"),
+            Err(Error::ScopeNotEntered { .. })
+        ));
+    }
+
+    #[test]
+    fn nested_scopes() {
+        let mut container = Container::new();
+        container.register("session", Scoped::new("session", |_| Ok(String::new())));
+        container.register("request", Scoped::new("request", |_| Ok(String::new())));
+
+        let session = container.enter_scope("session");
+        let first = session.enter_scope("request");
+        let shared = first.get::<String>("session").unwrap();
+        let request = first.get::<String>("request").unwrap();
+        assert!(Arc::ptr_eq(&shared, &session.get("session").unwrap()));
+        assert!(Arc::ptr_eq(&request, &first.get("request").unwrap()));
+
+        let second = session.enter_scope("request");
+        assert!(Arc::ptr_eq(&shared, &second.get("session").unwrap()));
+        assert!(!Arc::ptr_eq(&request, &second.get("request").unwrap()));
+
+        let inner = first.enter_scope("request");
+        assert!(!Arc::ptr_eq(&request, &inner.get("request").unwrap()));
+        assert!(Arc::ptr_eq(&request, &first.get("request").unwrap()));
+    }
+
+    #[test]
+    fn singleton_is_reset_through_handle() {
+        let mut container = Container::new();
+        let singleton = container.register("value", Singleton::new(|_| Ok(String::new())));
+        let first = container.get::<String>("value").unwrap();
+        assert!(Arc::ptr_eq(&first, &container.get("value").unwrap()));
+        singleton.reset();
+        assert!(!Arc::ptr_eq(&first, &container.get("value").unwrap()));
+    }
+
+    #[test]
+    fn singleton_is_created_once_by_concurrent_threads() {
+        const THREADS: usize = 8;
+        let created = Arc::new(AtomicUsize::new(0));
+        let mut container = Container::new();
+        let counter = created.clone();
+        container.register(
+            "value",
+            Singleton::new(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                thread::sleep(std::time::Duration::from_millis(10));
+                Ok(String::new())
+            }),
+        );
+        let barrier = Barrier::new(THREADS);
+        let instances: Vec<Arc<String>> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    scope.spawn(|| {
+                        barrier.wait();
+                        container.get::<String>("value").unwrap()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+        assert!(instances.iter().all(|i| Arc::ptr_eq(i, &instances[0])));
+    }
+}
@@ -0,0 +1,5 @@
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+
+create_exception!(inj, Error, PyException);
+create_exception!(inj, CircularDependencyError, Error);
@@ -0,0 +1,305 @@
+use crate::providers::{self, Aggregate, Provider, Selector};
+use pyo3::intern;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyType};
+use std::collections::HashMap;
+
+/// Provider of the graph
+struct Node {
+    id: String,
+    name: String,
+    kind: String,
+    provides: Option<String>,
+}
+
+/// Dependency of a provider on another one
+struct Edge {
+    source: String,
+    target: String,
+    kind: &'static str,
+    name: Option<String>,
+}
+
+impl Edge {
+    fn label(&self) -> String {
+        match (&self.name, self.kind) {
+            (Some(name), "arg") => format!("arg {name}"),
+            (Some(name), _) => name.clone(),
+            (None, kind) => kind.to_owned(),
+        }
+    }
+}
+
+/// Dependency graph of container providers.
+///
+/// Nodes are providers, edges point from a provider to its dependencies
+/// and are labeled as positional arguments, keyword arguments, selector
+/// and aggregate branches by key, overrides or plain dependencies.
+#[pyclass(module = "inj")]
+pub struct Graph {
+    name: String,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+#[pymethods]
+impl Graph {
+    /// Return graph name.
+    #[getter]
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Return nodes as dictionaries with id, name, type and provides.
+    #[getter]
+    fn nodes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let nodes = PyList::empty_bound(py);
+        for node in &self.nodes {
+            let item = PyDict::new_bound(py);
+            item.set_item("id", &node.id)?;
+            item.set_item("name", &node.name)?;
+            item.set_item("type", &node.kind)?;
+            item.set_item("provides", &node.provides)?;
+            nodes.append(item)?;
+        }
+        Ok(nodes)
+    }
+
+    /// Return edges as dictionaries with source, target, kind and name.
+    #[getter]
+    fn edges<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let edges = PyList::empty_bound(py);
+        for edge in &self.edges {
+            let item = PyDict::new_bound(py);
+            item.set_item("source", &edge.source)?;
+            item.set_item("target", &edge.target)?;
+            item.set_item("kind", edge.kind)?;
+            item.set_item("name", &edge.name)?;
+            edges.append(item)?;
+        }
+        Ok(edges)
+    }
+
+    /// Render graph in Graphviz DOT format.
+    fn to_dot(&self) -> String {
+        let escape = |value: &str| value.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut lines = vec![format!("digraph \"{}\" {{", escape(&self.name))];
+        lines.push("    node [shape=box];".to_owned());
+        for node in &self.nodes {
+            let mut label = format!("{}\\n{}", escape(&node.name), escape(&node.kind));
+            if let Some(provides) = &node.provides {
+                label.push_str(&format!("\\n{}", escape(provides)));
+            }
+            lines.push(format!("    {} [label=\"{}\"];", node.id, label));
+        }
+        for edge in &self.edges {
+            lines.push(format!(
+                "    {} -> {} [label=\"{}\"];",
+                edge.source,
+                edge.target,
+                escape(&edge.label())
+            ));
+        }
+        lines.push("}".to_owned());
+        lines.join("\n")
+    }
+
+    /// Render graph as Mermaid flowchart.
+    fn to_mermaid(&self) -> String {
+        let escape = |value: &str| value.replace('"', "#quot;").replace('|', "#124;");
+        let mut lines = vec!["graph LR".to_owned()];
+        for node in &self.nodes {
+            let mut label = format!("{}<br/>{}", escape(&node.name), escape(&node.kind));
+            if let Some(provides) = &node.provides {
+                label.push_str(&format!("<br/>{}", escape(provides)));
+            }
+            lines.push(format!("    {}[\"{}\"]", node.id, label));
+        }
+        for edge in &self.edges {
+            lines.push(format!(
+                "    {} -->|{}| {}",
+                edge.source,
+                escape(&edge.label()),
+                edge.target
+            ));
+        }
+        lines.join("\n")
+    }
+
+    /// Render graph as JSON document with nodes and edges.
+    #[pyo3(signature = (indent=None))]
+    fn to_json(&self, py: Python, indent: Option<usize>) -> PyResult<String> {
+        let document = PyDict::new_bound(py);
+        document.set_item("name", &self.name)?;
+        document.set_item("nodes", self.nodes(py)?)?;
+        document.set_item("edges", self.edges(py)?)?;
+        let kwargs = PyDict::new_bound(py);
+        kwargs.set_item("indent", indent)?;
+        py.import_bound(intern!(py, "json"))?
+            .call_method("dumps", (document,), Some(&kwargs))?
+            .extract()
+    }
+}
+
+impl Graph {
+    /// Build graph of providers reachable from the named root providers
+    pub(crate) fn build(
+        py: Python,
+        name: String,
+        roots: Vec<(String, PyObject)>,
+    ) -> PyResult<Self> {
+        let mut builder = Builder {
+            ids: HashMap::new(),
+            names: roots
+                .iter()
+                .map(|(name, provider)| (provider.as_ptr() as usize, name.clone()))
+                .collect(),
+            graph: Self {
+                name,
+                nodes: Vec::new(),
+                edges: Vec::new(),
+            },
+        };
+        for (_, provider) in &roots {
+            builder.visit(provider.bind(py))?;
+        }
+        Ok(builder.graph)
+    }
+}
+
+struct Builder {
+    ids: HashMap<usize, String>,
+    names: HashMap<usize, String>,
+    graph: Graph,
+}
+
+impl Builder {
+    /// Add node of the provider and its dependencies, returning node id
+    fn visit(&mut self, provider: &Bound<'_, PyAny>) -> PyResult<String> {
+        let key = provider.as_ptr() as usize;
+        if let Some(id) = self.ids.get(&key) {
+            return Ok(id.clone());
+        }
+        let id = format!("n{}", self.ids.len());
+        self.ids.insert(key, id.clone());
+        let name = match self.names.get(&key) {
+            Some(name) => name.clone(),
+            None => providers::provider_label(provider)?,
+        };
+        self.graph.nodes.push(Node {
+            id: id.clone(),
+            name,
+            kind: provider.get_type().qualname()?,
+            provides: describe_provides(provider)?,
+        });
+
+        for (dependency, kind, name) in dependencies(provider)? {
+            let target = self.visit(&dependency)?;
+            self.graph.edges.push(Edge {
+                source: id.clone(),
+                target,
+                kind,
+                name,
+            });
+        }
+        Ok(id)
+    }
+}
+
+/// Dependency provider with edge kind and name
+type Labeled<'py> = (Bound<'py, PyAny>, &'static str, Option<String>);
+
+/// Return labeled dependencies of the provider.
+///
+/// Arguments, keyword arguments, branches of selectors and aggregates and
+/// overriding providers are recognized, the rest of `related` providers are
+/// plain dependencies.
+fn dependencies<'py>(provider: &Bound<'py, PyAny>) -> PyResult<Vec<Labeled<'py>>> {
+    let py = provider.py();
+    let mut labeled = Vec::new();
+    if let Ok(args) = provider.getattr(intern!(py, "args")) {
+        if let Ok(args) = args.downcast::<PyList>() {
+            for (position, arg) in args.iter().enumerate() {
+                labeled.push((arg, "arg", Some(position.to_string())));
+            }
+        }
+    }
+    if let Ok(kwargs) = provider.getattr(intern!(py, "kwargs")) {
+        if let Ok(kwargs) = kwargs.downcast::<PyDict>() {
+            let mut kwargs: Vec<(String, Bound<'py, PyAny>)> = kwargs
+                .iter()
+                .map(|(name, value)| Ok((name.str()?.to_string(), value)))
+                .collect::<PyResult<_>>()?;
+            kwargs.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (name, value) in kwargs {
+                labeled.push((value, "kwarg", Some(name)));
+            }
+        }
+    }
+    if let Ok(selector) = provider.downcast::<Selector>() {
+        if let Some(selector) = selector.getattr(intern!(py, "selector"))?.extract()? {
+            labeled.push((selector, "selector", None));
+        }
+    }
+    if provider.is_instance_of::<Selector>() || provider.is_instance_of::<Aggregate>() {
+        let branches = provider.getattr(intern!(py, "providers"))?;
+        let mut branches: Vec<(String, Bound<'py, PyAny>)> = branches
+            .downcast::<PyDict>()?
+            .iter()
+            .map(|(key, branch)| Ok((key.str()?.to_string(), branch)))
+            .collect::<PyResult<_>>()?;
+        branches.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (key, branch) in branches {
+            labeled.push((branch, "branch", Some(key)));
+        }
+    }
+    if let Ok(provider) = provider.downcast::<Provider>() {
+        for overriding in provider.borrow().overridden.iter() {
+            labeled.push((overriding.bind(py).clone(), "override", None));
+        }
+    }
+    labeled.retain(|(dependency, _, _)| dependency.is_instance_of::<Provider>());
+
+    let mut dependencies = Vec::new();
+    for related in provider.getattr(intern!(py, "related"))?.iter()? {
+        let related = related?;
+        if !labeled
+            .iter()
+            .any(|(dependency, _, _)| dependency.is(&related))
+        {
+            dependencies.push((related, "dependency", None));
+        }
+    }
+    labeled.extend(dependencies);
+    Ok(labeled)
+}
+
+/// Return readable description of what the provider provides
+fn describe_provides(provider: &Bound<'_, PyAny>) -> PyResult<Option<String>> {
+    let py = provider.py();
+    let Ok(provides) = provider.getattr(intern!(py, "provides")) else {
+        return Ok(None);
+    };
+    if provides.is_none() || provides.is_instance_of::<Provider>() {
+        return Ok(None);
+    }
+    if let Ok(cls) = provides.downcast::<PyType>() {
+        let module: String = cls.getattr(intern!(py, "__module__"))?.extract()?;
+        let qualname = cls.qualname()?;
+        return Ok(Some(match module.as_str() {
+            "builtins" => qualname,
+            _ => format!("{module}.{qualname}"),
+        }));
+    }
+    if let (Ok(module), Ok(qualname)) = (
+        provides.getattr(intern!(py, "__module__")),
+        provides.getattr(intern!(py, "__qualname__")),
+    ) {
+        if let (Ok(module), Ok(qualname)) =
+            (module.extract::<String>(), qualname.extract::<String>())
+        {
+            return Ok(Some(format!("{module}.{qualname}")));
+        }
+    }
+    Ok(Some(provides.repr()?.to_string()))
+}
@@ -1,15 +1,73 @@
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
 
+pub mod core;
+
+#[cfg(feature = "python")]
+mod awaitables;
+#[cfg(feature = "python")]
 mod containers;
+#[cfg(feature = "python")]
+mod errors;
+#[cfg(feature = "python")]
+mod graph;
+#[cfg(feature = "python")]
 mod providers;
+#[cfg(feature = "python")]
 mod schema;
+#[cfg(feature = "python")]
+mod scopes;
+#[cfg(feature = "python")]
+mod tracing;
+#[cfg(feature = "python")]
+mod wiring;
+
+/// Make `inj` module importable by the interpreter embedded into the
+/// process, must be called before the interpreter is initialized.
+#[cfg(feature = "python")]
+pub fn append_to_inittab() {
+    pyo3::append_to_inittab!(inj);
+}
 
+#[cfg(feature = "python")]
 #[pymodule]
 fn inj(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("Error", m.py().get_type_bound::<errors::Error>())?;
+    m.add(
+        "CircularDependencyError",
+        m.py().get_type_bound::<errors::CircularDependencyError>(),
+    )?;
     m.add_class::<providers::Provider>()?;
     m.add_class::<providers::Dependency>()?;
-    m.add_class::<providers::DependenciesContainer>()?;
+    providers::add_dependencies_container(m)?;
+    m.add_class::<providers::Container>()?;
+    m.add_class::<providers::Resource>()?;
+    m.add_class::<providers::Callable>()?;
+    m.add_class::<providers::Coroutine>()?;
+    m.add_class::<providers::Factory>()?;
+    m.add_class::<providers::Singleton>()?;
+    m.add_class::<providers::AbstractFactory>()?;
+    m.add_class::<providers::AbstractSingleton>()?;
+    m.add_class::<providers::Scoped>()?;
+    m.add_class::<providers::Object>()?;
+    providers::add_self_provider(m)?;
+    m.add_class::<providers::List>()?;
+    m.add_class::<providers::Dict>()?;
+    m.add_class::<providers::Selector>()?;
+    m.add_class::<providers::Aggregate>()?;
+    m.add_class::<providers::FactoryAggregate>()?;
+    m.add_class::<providers::Delegate>()?;
+    m.add_class::<providers::ProvidedInstance>()?;
+    m.add_class::<providers::AttributeGetter>()?;
+    m.add_class::<providers::ItemGetter>()?;
+    m.add_class::<providers::MethodCaller>()?;
     m.add_class::<containers::WiringConfiguration>()?;
     m.add_class::<containers::DynamicContainer>()?;
+    m.add_class::<scopes::Scope>()?;
+    m.add_class::<graph::Graph>()?;
+    containers::add_declarative_container(m)?;
+    m.add_function(wrap_pyfunction!(containers::copy, m)?)?;
+    wiring::add_wiring_module(m)?;
+    tracing::add_tracing_module(m)?;
     Ok(())
 }
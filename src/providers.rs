@@ -1,197 +1,3645 @@
+use crate::awaitables::{gather, is_awaitable, Awaitable, Resume, Step, Waiters};
+use crate::containers::{self, DynamicContainer};
+use crate::core;
+use crate::errors;
+use crate::scopes::{self, Scope};
+use crate::tracing::{self, Trace};
+use indexmap::IndexMap;
+use pyo3::exceptions::{
+    PyAttributeError, PyNotImplementedError, PyStopAsyncIteration, PyStopIteration,
+};
+use pyo3::ffi;
+use pyo3::intern;
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyIterator, PyTuple, PyType};
+use pyo3::pyclass_init::PyClassInitializer;
+use pyo3::types::{PyDict, PyIterator, PyList, PyModule, PyString, PyTuple, PyType};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
 
 #[pyclass(module = "inj", subclass)]
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Provider {
     #[pyo3(get, set)]
     pub overridden: Vec<PyObject>,
     last_overriding: Option<PyObject>,
     overrides: Vec<PyObject>,
-    // async_mode: AsyncMode,
+    async_mode: AsyncMode,
+    /// Name in the first container provider is added to, used in messages
+    name: Option<String>,
+    /// Weak reference to that container
+    owner: Option<PyObject>,
 }
 
-impl std::default::Default for Provider {
-    fn default() -> Self {
+/// Whether provider returns awaitables
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum AsyncMode {
+    /// Detected on the first call
+    #[default]
+    Undefined,
+    Enabled,
+    Disabled,
+}
+
+#[pymethods]
+impl Provider {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return provided object.
+    ///
+    /// Last overriding provider takes precedence over the provider itself.
+    #[pyo3(signature = (*args, **kwargs))]
+    fn __call__(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let last_overriding = slf.borrow().last_overriding.clone();
+        let resolving = Resolving::enter(slf.as_any())?;
+        let result = match last_overriding {
+            Some(overriding) => overriding.bind(py).call(args, kwargs),
+            None => slf.call_method1(intern!(py, "_provide"), (args, kwargs)),
+        }
+        .and_then(|result| Self::apply_async_mode(slf, result));
+        resolving.finish(py, result)
+    }
+
+    /// Provide object, must be implemented by subclasses.
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        _args: &Bound<'_, PyTuple>,
+        _kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        Err(PyNotImplementedError::new_err(format!(
+            "Provider \"{}\" does not implement _provide()",
+            provider_label(slf.as_any())?
+        )))
+    }
+
+    /// Return delegate of the provider.
+    ///
+    /// Delegate injects the provider itself instead of its result.
+    fn delegate<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, Delegate>> {
+        Delegate::of(slf.as_any())
+    }
+
+    /// Return delegate of the provider, same as `delegate()`.
+    #[getter]
+    fn provider<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, Delegate>> {
+        Delegate::of(slf.as_any())
+    }
+
+    /// Return builder of lazy attribute, item and method call chains over
+    /// the provided object.
+    #[getter]
+    fn provided<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, ProvidedInstance>> {
+        let provided = ProvidedInstance::new(Some(slf.clone().into_any().unbind()));
+        Bound::new(slf.py(), provided)
+    }
+
+    /// Override provider with another provider.
+    ///
+    /// Returns context manager that resets the last overriding on exit.
+    fn r#override(
+        slf: &Bound<'_, Self>,
+        provider: &Bound<'_, PyAny>,
+    ) -> PyResult<OverridingContext> {
+        if provider.is(slf) {
+            return Err(errors::Error::new_err(format!(
+                "Provider \"{}\" could not be overridden with itself",
+                provider_label(slf.as_any())?
+            )));
+        }
+        if !provider.is_callable() {
+            return Err(errors::Error::new_err(format!(
+                "Provider \"{}\" expected to get callable provider, got {}",
+                provider_label(slf.as_any())?,
+                provider.repr()?
+            )));
+        }
+
+        {
+            let mut this = slf.borrow_mut();
+            this.overridden.push(provider.clone().unbind());
+            this.last_overriding = Some(provider.clone().unbind());
+        }
+        Factory::invalidate_plan(slf.as_any());
+        if let Ok(overriding) = provider.downcast::<Provider>() {
+            overriding
+                .borrow_mut()
+                .overrides
+                .push(slf.clone().into_any().unbind());
+        }
+
+        Ok(OverridingContext {
+            overridden: slf.clone().into_any().unbind(),
+            overriding: provider.clone().unbind(),
+        })
+    }
+
+    /// Reset last overriding provider.
+    fn reset_last_overriding(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let popped = {
+            let mut this = slf.borrow_mut();
+            let popped = this.overridden.pop();
+            this.last_overriding = this.overridden.last().map(|p| p.clone_ref(py));
+            popped
+        };
+        Factory::invalidate_plan(slf.as_any());
+        match popped {
+            Some(provider) => unregister_overrides(slf, provider.bind(py)),
+            None => Err(errors::Error::new_err(format!(
+                "Provider \"{}\" is not overridden",
+                provider_label(slf.as_any())?
+            ))),
+        }
+    }
+
+    /// Reset all overriding providers.
+    fn reset_override(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let overridden = {
+            let mut this = slf.borrow_mut();
+            this.last_overriding = None;
+            std::mem::take(&mut this.overridden)
+        };
+        Factory::invalidate_plan(slf.as_any());
+        for provider in overridden {
+            unregister_overrides(slf, provider.bind(py))?;
+        }
+        Ok(())
+    }
+
+    /// Enable async mode, provider always returns awaitables.
+    fn enable_async_mode(&mut self) {
+        self.async_mode = AsyncMode::Enabled;
+    }
+
+    /// Disable async mode, results and injections are never awaited.
+    fn disable_async_mode(&mut self) {
+        self.async_mode = AsyncMode::Disabled;
+    }
+
+    /// Reset async mode, it is detected again on the next call.
+    fn reset_async_mode(&mut self) {
+        self.async_mode = AsyncMode::Undefined;
+    }
+
+    /// Check if async mode is enabled.
+    fn is_async_mode_enabled(&self) -> bool {
+        self.async_mode == AsyncMode::Enabled
+    }
+
+    /// Check if async mode is disabled.
+    fn is_async_mode_disabled(&self) -> bool {
+        self.async_mode == AsyncMode::Disabled
+    }
+
+    /// Check if async mode is not detected yet.
+    fn is_async_mode_undefined(&self) -> bool {
+        self.async_mode == AsyncMode::Undefined
+    }
+
+    /// Check if provider is overridden by another provider.
+    #[getter]
+    fn is_overridden(&self) -> bool {
+        !self.overridden.is_empty()
+    }
+
+    /// Return last overriding provider.
+    #[getter]
+    fn last_overriding(&self) -> Option<PyObject> {
+        self.last_overriding.clone()
+    }
+
+    /// Return providers that are overridden by this provider.
+    #[getter]
+    fn overrides(&self) -> Vec<PyObject> {
+        self.overrides.clone()
+    }
+
+    /// Return related providers generator.
+    #[getter]
+    fn related(&self, py: Python) -> Vec<PyObject> {
+        providers_only(py, self.overridden.iter())
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |_| Ok(()))
+    }
+}
+
+impl Provider {
+    pub(crate) fn last_overriding_ref(&self) -> Option<&PyObject> {
+        self.last_overriding.as_ref()
+    }
+
+    /// Detect async mode on the first call and make results of providers
+    /// in async mode awaitable.
+    pub(crate) fn apply_async_mode(
+        slf: &Bound<'_, Self>,
+        result: Bound<'_, PyAny>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let async_mode = slf.borrow().async_mode;
+        match async_mode {
+            AsyncMode::Disabled => Ok(result.unbind()),
+            AsyncMode::Enabled if is_awaitable(&result) => Ok(result.unbind()),
+            AsyncMode::Enabled => {
+                let ready = Awaitable::ready(result.unbind());
+                Ok(Bound::new(py, ready)?.into_any().unbind())
+            }
+            AsyncMode::Undefined => {
+                slf.borrow_mut().async_mode = if is_awaitable(&result) {
+                    AsyncMode::Enabled
+                } else {
+                    AsyncMode::Disabled
+                };
+                Ok(result.unbind())
+            }
+        }
+    }
+
+    /// Remember name of the provider in a container, unless it has one
+    pub(crate) fn set_name(provider: &Bound<'_, PyAny>, name: &str) {
+        if let Ok(provider) = provider.downcast::<Provider>() {
+            provider
+                .borrow_mut()
+                .name
+                .get_or_insert_with(|| name.to_owned());
+        }
+    }
+
+    /// Remember container the provider is named in, unless it has one
+    pub(crate) fn set_owner(provider: &Bound<'_, PyAny>, owner: &Bound<'_, PyAny>) -> PyResult<()> {
+        let py = provider.py();
+        if let Ok(provider) = provider.downcast::<Provider>() {
+            if provider.borrow().owner.is_none() {
+                let owner = py
+                    .import_bound(intern!(py, "weakref"))?
+                    .call_method1(intern!(py, "ref"), (owner,))?;
+                provider.borrow_mut().owner = Some(owner.unbind());
+            }
+        }
+        Ok(())
+    }
+
+    /// Return name of the provider in the first container it was added to
+    pub(crate) fn name_of(provider: &Bound<'_, PyAny>) -> Option<String> {
+        let provider = provider.downcast::<Provider>().ok()?;
+        let name = provider.borrow().name.clone();
+        name
+    }
+
+    /// Check if awaitable injections of provider are awaited before the call
+    pub(crate) fn awaits_injections(provider: &Bound<'_, PyAny>) -> bool {
+        match provider.downcast::<Provider>() {
+            Ok(provider) => provider.borrow().async_mode != AsyncMode::Disabled,
+            Err(_) => true,
+        }
+    }
+
+    /// Copy overriding stack of provider into its copy.
+    pub(crate) fn copy_overridings(
+        slf: &Bound<'_, Self>,
+        copied: &Bound<'_, PyAny>,
+        memo: &Bound<'_, PyDict>,
+    ) -> PyResult<()> {
+        let (overridden, overrides) = {
+            let this = slf.borrow();
+            (this.overridden.clone(), this.overrides.clone())
+        };
+        let overridden: Vec<PyObject> =
+            deepcopy(&overridden.into_py(slf.py()).into_bound(slf.py()), memo)?.extract()?;
+        let overrides: Vec<PyObject> =
+            deepcopy(&overrides.into_py(slf.py()).into_bound(slf.py()), memo)?.extract()?;
+
+        let (async_mode, name) = {
+            let this = slf.borrow();
+            (this.async_mode, this.name.clone())
+        };
+        let mut copied = copied.downcast::<Provider>()?.borrow_mut();
+        copied.async_mode = async_mode;
+        copied.name = name;
+        copied.last_overriding = overridden.last().cloned();
+        copied.overridden = overridden;
+        copied.overrides = overrides;
+        Ok(())
+    }
+}
+
+/// Deep copy object sharing the memo, so shared providers stay shared.
+pub(crate) fn deepcopy<'py>(
+    obj: &Bound<'py, PyAny>,
+    memo: &Bound<'py, PyDict>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let py = obj.py();
+    py.import_bound(intern!(py, "copy"))?
+        .call_method1(intern!(py, "deepcopy"), (obj, memo))
+}
+
+/// Return copy of object if it is already in the memo.
+pub(crate) fn memo_get<'py>(
+    obj: &Bound<'py, PyAny>,
+    memo: &Bound<'py, PyDict>,
+) -> PyResult<Option<Bound<'py, PyAny>>> {
+    memo.get_item(obj.as_ptr() as usize)
+}
+
+/// Register copy of object in the memo.
+pub(crate) fn memo_set(
+    obj: &Bound<'_, PyAny>,
+    copied: &Bound<'_, PyAny>,
+    memo: &Bound<'_, PyDict>,
+) -> PyResult<()> {
+    memo.set_item(obj.as_ptr() as usize, copied)
+}
+
+/// Copy parent of a child provider.
+///
+/// Providers and dynamic containers are deep-copied, declarative container
+/// classes are shared.
+pub(crate) fn copy_parent(
+    parent: Option<PyObject>,
+    memo: &Bound<'_, PyDict>,
+) -> PyResult<Option<PyObject>> {
+    let py = memo.py();
+    match parent {
+        Some(parent)
+            if parent.bind(py).is_instance_of::<Provider>()
+                || parent.bind(py).is_instance_of::<DynamicContainer>() =>
+        {
+            Ok(Some(deepcopy(parent.bind(py), memo)?.unbind()))
+        }
+        parent => Ok(parent),
+    }
+}
+
+fn deepcopy_option(obj: Option<PyObject>, memo: &Bound<'_, PyDict>) -> PyResult<Option<PyObject>> {
+    match obj {
+        Some(obj) => Ok(Some(deepcopy(obj.bind(memo.py()), memo)?.unbind())),
+        None => Ok(None),
+    }
+}
+
+/// Deep copy provider into a new instance of its type.
+///
+/// The copy is registered in the memo before `copy_state` fills it, so
+/// cyclic references resolve to the copy. Overridings are copied last.
+fn deepcopy_provider<'py, T: pyo3::PyClass>(
+    slf: &Bound<'py, T>,
+    memo: &Bound<'py, PyDict>,
+    copy_state: impl FnOnce(&Bound<'py, T>) -> PyResult<()>,
+) -> PyResult<Bound<'py, PyAny>> {
+    if let Some(copied) = memo_get(slf.as_any(), memo)? {
+        return Ok(copied);
+    }
+    let copied = slf.as_any().get_type().call0()?;
+    memo_set(slf.as_any(), &copied, memo)?;
+    copy_state(copied.downcast::<T>()?)?;
+    Provider::copy_overridings(slf.as_any().downcast()?, &copied, memo)?;
+    Ok(copied)
+}
+
+/// Positional and keyword argument injections of a provider.
+///
+/// Provider injections are called on every call, other values are passed as
+/// is.
+#[derive(Clone, Default)]
+pub(crate) struct Injections {
+    args: Vec<PyObject>,
+    kwargs: IndexMap<String, PyObject>,
+}
+
+impl Injections {
+    pub(crate) fn new(args: Vec<PyObject>, kwargs: Option<IndexMap<String, PyObject>>) -> Self {
+        Self {
+            args,
+            kwargs: kwargs.unwrap_or_default(),
+        }
+    }
+
+    fn add_args(&mut self, args: Vec<PyObject>) {
+        self.args.extend(args);
+    }
+
+    fn set_args(&mut self, args: Vec<PyObject>) {
+        self.args = args;
+    }
+
+    fn clear_args(&mut self) {
+        self.args.clear();
+    }
+
+    fn add_kwargs(&mut self, kwargs: Option<IndexMap<String, PyObject>>) {
+        self.kwargs.extend(kwargs.unwrap_or_default());
+    }
+
+    fn set_kwargs(&mut self, kwargs: Option<IndexMap<String, PyObject>>) {
+        self.kwargs = kwargs.unwrap_or_default();
+    }
+
+    fn clear_kwargs(&mut self) {
+        self.kwargs.clear();
+    }
+
+    /// Iterate over positional injections, then keyword ones
+    fn values(&self) -> impl Iterator<Item = &PyObject> {
+        self.args.iter().chain(self.kwargs.values())
+    }
+
+    /// Resolve injections and merge them with call arguments.
+    ///
+    /// Call arguments are appended to positional injections and override
+    /// keyword injections.
+    fn call_args<'py>(
+        &self,
+        py: Python<'py>,
+        args: &Bound<'py, PyTuple>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<(Bound<'py, PyTuple>, Bound<'py, PyDict>)> {
+        let mut call_args = Vec::with_capacity(self.args.len() + args.len());
+        for injection in &self.args {
+            call_args.push(provide_injection(injection.bind(py))?);
+        }
+        call_args.extend(args.iter());
+
+        let call_kwargs = PyDict::new_bound(py);
+        for (name, injection) in &self.kwargs {
+            call_kwargs.set_item(name, provide_injection(injection.bind(py))?)?;
+        }
+        if let Some(kwargs) = kwargs {
+            call_kwargs.update(kwargs.as_mapping())?;
+        }
+        Ok((PyTuple::new_bound(py, call_args), call_kwargs))
+    }
+
+    /// Deep copy injections sharing the memo
+    fn deepcopy(&self, memo: &Bound<'_, PyDict>) -> PyResult<Self> {
+        let py = memo.py();
+        Ok(Self {
+            args: deepcopy(self.args.clone().into_py(py).bind(py), memo)?.extract()?,
+            kwargs: deepcopy(self.kwargs.clone().into_py(py).bind(py), memo)?.extract()?,
+        })
+    }
+}
+
+fn unregister_overrides(slf: &Bound<'_, Provider>, provider: &Bound<'_, PyAny>) -> PyResult<()> {
+    if let Ok(overriding) = provider.downcast::<Provider>() {
+        overriding
+            .borrow_mut()
+            .overrides
+            .retain(|overridden| !overridden.is(slf));
+    }
+    Ok(())
+}
+
+/// Filter out everything that is not a provider
+pub(crate) fn providers_only<'a>(
+    py: Python,
+    objects: impl Iterator<Item = &'a PyObject>,
+) -> Vec<PyObject> {
+    objects
+        .filter(|obj| obj.bind(py).is_instance_of::<Provider>())
+        .map(|obj| obj.clone_ref(py))
+        .collect()
+}
+
+/// Return human readable provider name for error messages.
+///
+/// Full dotted path is used when provider is attached to a container,
+/// e.g. ``AppContainer.gateways.database``.
+pub(crate) fn provider_label(provider: &Bound<'_, PyAny>) -> PyResult<String> {
+    // Provided instance chains build getters for unknown attributes, so a
+    // value of `parent_name` other than a string is ignored
+    if let Ok(parent_name) = provider.getattr(intern!(provider.py(), "parent_name")) {
+        if let Ok(Some(parent_name)) = parent_name.extract::<Option<String>>() {
+            return Ok(parent_name);
+        }
+    }
+    if let Ok(provider) = provider.downcast::<Provider>() {
+        if let Some(name) = &provider.borrow().name {
+            return Ok(name.clone());
+        }
+    }
+    provider.get_type().qualname()
+}
+
+/// Return full dotted path of the provider, e.g.
+/// ``AppContainer.gateways.database``.
+///
+/// Providers without a parent are prefixed with the path of the container
+/// they are named in.
+pub(crate) fn provider_path(provider: &Bound<'_, PyAny>) -> PyResult<String> {
+    let py = provider.py();
+    if let Ok(parent_name) = provider.getattr(intern!(py, "parent_name")) {
+        if let Ok(Some(parent_name)) = parent_name.extract::<Option<String>>() {
+            return Ok(parent_name);
+        }
+    }
+    if let Ok(provider) = provider.downcast::<Provider>() {
+        let (name, owner) = {
+            let provider = provider.borrow();
+            let owner = provider.owner.as_ref().map(|owner| owner.clone_ref(py));
+            (provider.name.clone(), owner)
+        };
+        if let (Some(name), Some(owner)) = (name, owner) {
+            let owner = owner.call0(py)?.into_bound(py);
+            if !owner.is_none() {
+                return Ok(format!("{}.{name}", containers::container_label(&owner)?));
+            }
+        }
+    }
+    provider_label(provider)
+}
+
+thread_local! {
+    /// Providers being called by the thread, innermost last
+    static RESOLVING: RefCell<Vec<PyObject>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Entry of the resolution stack, removed on drop.
+struct Resolving {
+    trace: Option<Trace>,
+}
+
+impl Resolving {
+    /// Push provider to the resolution stack.
+    ///
+    /// Fails if provider is already being resolved, as the call would
+    /// recurse endlessly.
+    fn enter(provider: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let mut depth = 0;
+        let cycle = RESOLVING.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            match stack.iter().position(|resolving| resolving.is(provider)) {
+                Some(position) => Some(stack[position..].to_vec()),
+                None => {
+                    depth = stack.len();
+                    stack.push(provider.clone().unbind());
+                    None
+                }
+            }
+        });
+        match cycle {
+            Some(cycle) => {
+                let py = provider.py();
+                let cycle: Vec<Bound<'_, PyAny>> = cycle
+                    .into_iter()
+                    .map(|provider| provider.into_bound(py))
+                    .chain(std::iter::once(provider.clone()))
+                    .collect();
+                Err(circular_dependency_error(&cycle, provider_label)?)
+            }
+            None => Ok(Self {
+                trace: Trace::start(provider, depth),
+            }),
+        }
+    }
+
+    /// Report the resolution result to tracing hooks and return it
+    fn finish(mut self, py: Python, result: PyResult<PyObject>) -> PyResult<PyObject> {
+        if let Some(trace) = self.trace.take() {
+            trace.finish(py, &result);
+        }
+        result
+    }
+}
+
+impl Drop for Resolving {
+    fn drop(&mut self) {
+        RESOLVING.with(|stack| stack.borrow_mut().pop());
+    }
+}
+
+/// Create error describing the dependency cycle, e.g. ``a -> b -> a``.
+pub(crate) fn circular_dependency_error(
+    cycle: &[Bound<'_, PyAny>],
+    label: impl Fn(&Bound<'_, PyAny>) -> PyResult<String>,
+) -> PyResult<PyErr> {
+    let path = cycle.iter().map(label).collect::<PyResult<Vec<_>>>()?;
+    Ok(errors::CircularDependencyError::new_err(
+        core::Error::CircularDependency(path).to_string(),
+    ))
+}
+
+/// Compute dotted path of a child provider using its parent.
+pub(crate) fn resolve_parent_name(
+    parent: Option<&PyObject>,
+    provider: &Bound<'_, PyAny>,
+) -> PyResult<Option<String>> {
+    let py = provider.py();
+    let Some(parent) = parent else {
+        return Ok(None);
+    };
+    let parent = parent.bind(py);
+    let provider_name: String = parent
+        .call_method1(intern!(py, "resolve_provider_name"), (provider,))?
+        .extract()?;
+    match parent
+        .getattr(intern!(py, "parent_name"))?
+        .extract::<Option<String>>()?
+    {
+        Some(parent_name) => Ok(Some(format!("{parent_name}.{provider_name}"))),
+        None => Ok(Some(provider_name)),
+    }
+}
+
+/// Context manager returned by `Provider.override()`
+#[pyclass(module = "inj")]
+pub struct OverridingContext {
+    overridden: PyObject,
+    overriding: PyObject,
+}
+
+#[pymethods]
+impl OverridingContext {
+    fn __enter__(&self, py: Python) -> PyObject {
+        self.overriding.clone_ref(py)
+    }
+
+    #[pyo3(signature = (*_exc_info))]
+    fn __exit__(&self, py: Python, _exc_info: &Bound<'_, PyTuple>) -> PyResult<()> {
+        self.overridden
+            .call_method0(py, intern!(py, "reset_last_overriding"))?;
+        Ok(())
+    }
+}
+
+#[pyclass(extends=Provider, module="inj", subclass)]
+#[derive(Default)]
+pub struct Dependency {
+    instance_of: Option<Py<PyType>>,
+    default: Option<Py<PyAny>>,
+    parent: Option<PyObject>,
+}
+
+#[pymethods]
+impl Dependency {
+    #[new]
+    #[pyo3(signature = (instance_of=None, default=None, **kwargs))]
+    fn new(
+        instance_of: Option<Py<PyType>>,
+        default: Option<Py<PyAny>>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<(Self, Provider)> {
+        let parent = match kwargs {
+            Some(kwargs) => kwargs.get_item("__parent__")?.map(Bound::unbind),
+            None => None,
+        };
+        let this = Self {
+            instance_of,
+            default,
+            parent,
+        };
+        let base = Provider::new();
+        Ok((this, base))
+    }
+
+    /// Return provided instance.
+    ///
+    /// Raises error if dependency is neither overridden nor has a default.
+    #[pyo3(signature = (*args, **kwargs))]
+    fn __call__(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let (last_overriding, default, instance_of) = {
+            let this = slf.borrow();
+            let base: &Provider = this.as_ref();
+            (
+                base.last_overriding_ref().map(|p| p.clone_ref(py)),
+                this.default.as_ref().map(|p| p.clone_ref(py)),
+                this.instance_of.as_ref().map(|p| p.clone_ref(py)),
+            )
+        };
+
+        let resolving = Resolving::enter(slf.as_any())?;
+        let result = (|| {
+            let result = match (last_overriding, default) {
+                (Some(overriding), _) => overriding.bind(py).call(args, kwargs)?,
+                (None, Some(default)) if default.bind(py).is_instance_of::<Provider>() => {
+                    default.bind(py).call(args, kwargs)?
+                }
+                (None, Some(default)) => default.into_bound(py),
+                (None, None) => {
+                    return Err(match Self::parent_name(slf)? {
+                        Some(name) => {
+                            errors::Error::new_err(format!("Dependency \"{name}\" is not defined"))
+                        }
+                        None => errors::Error::new_err("Dependency is not defined"),
+                    })
+                }
+            };
+
+            let result = match instance_of {
+                Some(instance_of) if is_awaitable(&result) => {
+                    let label = provider_label(slf.as_any())?;
+                    let checked = Awaitable::map(result.unbind(), move |py, result| {
+                        let result = result?.into_bound(py);
+                        Self::check_instance_of(&label, &result, instance_of.bind(py))?;
+                        Ok(result.unbind())
+                    });
+                    Bound::new(py, checked)?.into_any()
+                }
+                Some(instance_of) => {
+                    Self::check_instance_of(
+                        &provider_label(slf.as_any())?,
+                        &result,
+                        instance_of.bind(py),
+                    )?;
+                    result
+                }
+                None => result,
+            };
+            Provider::apply_async_mode(slf.downcast()?, result)
+        })();
+        resolving.finish(py, result)
+    }
+
+    /// Return class the dependency must be an instance of.
+    #[getter]
+    fn instance_of(&self) -> Option<Py<PyType>> {
+        self.instance_of.clone()
+    }
+
+    /// Return default value.
+    #[getter]
+    fn default(&self) -> Option<PyObject> {
+        self.default.clone()
+    }
+
+    /// Check if dependency is defined, either by overriding or by default.
+    #[getter]
+    fn is_defined(slf: &Bound<'_, Self>) -> bool {
+        let this = slf.borrow();
+        let base: &Provider = this.as_ref();
+        base.last_overriding_ref().is_some() || this.default.is_some()
+    }
+
+    /// Set external dependency provider.
+    fn provided_by(
+        slf: &Bound<'_, Self>,
+        provider: &Bound<'_, PyAny>,
+    ) -> PyResult<OverridingContext> {
+        Provider::r#override(slf.as_any().downcast::<Provider>()?, provider)
+    }
+
+    /// Return parent.
+    #[getter]
+    fn parent(&self) -> Option<PyObject> {
+        self.parent.clone()
+    }
+
+    /// Return parent name.
+    #[getter]
+    fn parent_name(slf: &Bound<'_, Self>) -> PyResult<Option<String>> {
+        let parent = slf.borrow().parent.clone();
+        resolve_parent_name(parent.as_ref(), slf.as_any())
+    }
+
+    /// Assign parent.
+    fn assign_parent(&mut self, parent: PyObject) {
+        self.parent = Some(parent);
+    }
+
+    #[getter]
+    fn related(slf: &Bound<'_, Self>) -> Vec<PyObject> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let base: &Provider = this.as_ref();
+        providers_only(py, this.default.iter().chain(base.overridden.iter()))
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let (instance_of, default, parent) = {
+                let this = slf.borrow();
+                (
+                    this.instance_of.clone(),
+                    this.default.clone(),
+                    this.parent.clone(),
+                )
+            };
+            let default = deepcopy_option(default, memo)?;
+            let parent = copy_parent(parent, memo)?;
+            let mut copied = copied.borrow_mut();
+            copied.instance_of = instance_of;
+            copied.default = default;
+            copied.parent = parent;
+            Ok(())
+        })
+    }
+}
+
+impl Dependency {
+    fn check_instance_of(
+        label: &str,
+        result: &Bound<'_, PyAny>,
+        instance_of: &Bound<'_, PyType>,
+    ) -> PyResult<()> {
+        if !result.is_instance(instance_of)? {
+            return Err(errors::Error::new_err(format!(
+                "Dependency \"{}\": {} is not an instance of {}",
+                label,
+                result.repr()?,
+                instance_of.qualname()?
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[pyclass(extends=Provider, module="inj", subclass)]
+pub struct DependenciesContainer {
+    providers: HashMap<String, PyObject>,
+    parent: Option<PyObject>,
+}
+
+#[pymethods]
+impl DependenciesContainer {
+    #[new]
+    #[pyo3(signature = (**dependencies))]
+    fn new(dependencies: Option<HashMap<String, PyObject>>) -> (Self, Provider) {
+        let this = DependenciesContainer {
+            providers: dependencies.unwrap_or_default(),
+            parent: None,
+        };
+        let base = Provider::new();
+        (this, base)
+    }
+
+    /// Assign itself as a parent of dependencies passed to the constructor.
+    #[pyo3(signature = (**_dependencies))]
+    fn __init__(slf: &Bound<'_, Self>, _dependencies: Option<&Bound<'_, PyDict>>) -> PyResult<()> {
+        let py = slf.py();
+        let providers: Vec<PyObject> = slf.borrow().providers.values().cloned().collect();
+        for provider in providers {
+            let provider = provider.bind(py);
+            if is_child_provider(provider)? {
+                provider.call_method1(intern!(py, "assign_parent"), (slf,))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Return dependency provider, creating it on first access.
+    fn __getattr__(slf: &Bound<'_, Self>, name: &str) -> PyResult<PyObject> {
+        let py = slf.py();
+        if name.starts_with("__") && name.ends_with("__") {
+            return Err(PyAttributeError::new_err(name.to_owned()));
+        }
+        if let Some(provider) = slf.borrow().providers.get(name) {
+            return Ok(provider.clone_ref(py));
+        }
+
+        let dependency = Bound::new(
+            py,
+            PyClassInitializer::from(Provider::new())
+                .add_subclass(<Dependency as Default>::default()),
+        )?;
+        dependency.borrow_mut().parent = Some(slf.clone().into_any().unbind());
+        let dependency = dependency.into_any().unbind();
+        slf.borrow_mut()
+            .providers
+            .insert(name.to_owned(), dependency.clone_ref(py));
+        Ok(dependency)
+    }
+
+    /// Return dependency providers dictionary.
+    #[getter]
+    fn providers(&self) -> HashMap<String, PyObject> {
+        self.providers.clone()
+    }
+
+    /// Override dependencies with providers of overriding container.
+    fn r#override(
+        slf: &Bound<'_, Self>,
+        provider: &Bound<'_, PyAny>,
+    ) -> PyResult<OverridingContext> {
+        let py = slf.py();
+        if let Ok(providers) = provider.getattr(intern!(py, "providers")) {
+            for (name, overriding) in providers.downcast::<PyDict>()?.iter() {
+                let name = name.extract::<String>()?;
+                let dependency = Self::__getattr__(slf, &name)?;
+                dependency.call_method1(py, intern!(py, "override"), (overriding,))?;
+            }
+        }
+        Provider::r#override(slf.as_any().downcast::<Provider>()?, provider)
+    }
+
+    /// Reset all overriding providers, including ones of dependencies.
+    fn reset_override(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let providers: Vec<PyObject> = slf.borrow().providers.values().cloned().collect();
+        for provider in providers {
+            provider.call_method0(py, intern!(py, "reset_override"))?;
+        }
+        Provider::reset_override(slf.as_any().downcast::<Provider>()?)
+    }
+
+    /// Try to resolve provider name
+    fn resolve_provider_name(&self, provider: &Bound<'_, PyAny>) -> PyResult<String> {
+        for (provider_name, container_provider) in &self.providers {
+            if container_provider.is(provider) {
+                return Ok(provider_name.to_owned());
+            }
+        }
+        Err(errors::Error::new_err(format!(
+            "Can not resolve name for provider \"{}\"",
+            provider.repr()?
+        )))
+    }
+
+    /// Return parent.
+    #[getter]
+    fn parent(&self) -> Option<PyObject> {
+        self.parent.clone()
+    }
+
+    /// Return parent name.
+    #[getter]
+    fn parent_name(slf: &Bound<'_, Self>) -> PyResult<Option<String>> {
+        let parent = slf.borrow().parent.clone();
+        resolve_parent_name(parent.as_ref(), slf.as_any())
+    }
+
+    /// Assign parent, also assigning itself as a parent of its dependencies.
+    fn assign_parent(slf: &Bound<'_, Self>, parent: PyObject) -> PyResult<()> {
+        let py = slf.py();
+        let providers: Vec<PyObject> = {
+            let mut this = slf.borrow_mut();
+            this.parent = Some(parent);
+            this.providers.values().cloned().collect()
+        };
+        for provider in providers {
+            let provider = provider.bind(py);
+            if is_child_provider(provider)? {
+                provider.call_method1(intern!(py, "assign_parent"), (slf,))?;
+            }
+        }
+        Ok(())
+    }
+
+    #[getter]
+    fn related(slf: &Bound<'_, Self>) -> Vec<PyObject> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let base: &Provider = this.as_ref();
+        providers_only(py, this.providers.values().chain(base.overridden.iter()))
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let py = slf.py();
+            let (providers, parent) = {
+                let this = slf.borrow();
+                (this.providers.clone(), this.parent.clone())
+            };
+            let providers = deepcopy(providers.into_py(py).bind(py), memo)?.extract()?;
+            let parent = copy_parent(parent, memo)?;
+            let mut copied = copied.borrow_mut();
+            copied.providers = providers;
+            copied.parent = parent;
+            Ok(())
+        })
+    }
+}
+
+// Container provider provides an instance of declarative container.
+#[pyclass(extends=Provider, module="inj", subclass)]
+pub struct Container {
+    container_cls: Option<Py<PyType>>,
+    container: Option<PyObject>,
+    overriding_providers: HashMap<String, PyObject>,
+    parent: Option<PyObject>,
+}
+
+#[pymethods]
+impl Container {
+    #[new]
+    #[pyo3(signature = (container_cls=None, container=None, **overriding_providers))]
+    fn new(
+        py: Python,
+        container_cls: Option<Py<PyType>>,
+        container: Option<PyObject>,
+        overriding_providers: Option<HashMap<String, PyObject>>,
+    ) -> PyResult<(Self, Provider)> {
+        let container = match (container, &container_cls) {
+            (Some(container), _) => Some(container),
+            (None, Some(container_cls)) => Some(container_cls.call0(py)?),
+            (None, None) => None,
+        };
+        let this = Container {
+            container_cls,
+            container,
+            overriding_providers: overriding_providers.unwrap_or_default(),
+            parent: None,
+        };
+        this.apply_overridings(py)?;
+        let base = Provider::new();
+        Ok((this, base))
+    }
+
+    /// Return container providers, if attribute is not found on provider.
+    fn __getattr__(&self, py: Python, name: &str) -> PyResult<PyObject> {
+        match &self.container {
+            Some(container) if !name.starts_with("__") => container.getattr(py, name),
+            _ => Err(PyAttributeError::new_err(name.to_owned())),
+        }
+    }
+
+    /// Return container.
+    #[getter]
+    fn container(&self) -> Option<PyObject> {
+        self.container.clone()
+    }
+
+    /// Return container class.
+    #[getter]
+    fn container_cls(&self) -> Option<Py<PyType>> {
+        self.container_cls.clone()
+    }
+
+    fn _provide(
+        &self,
+        py: Python,
+        _args: &Bound<'_, PyTuple>,
+        _kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyObject {
+        match &self.container {
+            Some(container) => container.clone_ref(py),
+            None => py.None(),
+        }
+    }
+
+    /// Apply container overriding.
+    ///
+    /// This method should not be called directly. It is called on
+    /// declarative container initialization.
+    fn apply_overridings(&self, py: Python) -> PyResult<()> {
+        let Some(container) = &self.container else {
+            return Ok(());
+        };
+        for (name, overriding_provider) in &self.overriding_providers {
+            container.getattr(py, name.as_str())?.call_method1(
+                py,
+                intern!(py, "override"),
+                (overriding_provider,),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Return parent.
+    #[getter]
+    fn parent(&self) -> Option<PyObject> {
+        self.parent.clone()
+    }
+
+    /// Return parent name.
+    #[getter]
+    fn parent_name(slf: &Bound<'_, Self>) -> PyResult<Option<String>> {
+        let parent = slf.borrow().parent.clone();
+        resolve_parent_name(parent.as_ref(), slf.as_any())
+    }
+
+    /// Assign parent, also becoming parent of the provided container.
+    fn assign_parent(slf: &Bound<'_, Self>, parent: PyObject) -> PyResult<()> {
+        let py = slf.py();
+        let container = {
+            let mut this = slf.borrow_mut();
+            this.parent = Some(parent);
+            this.container.clone()
+        };
+        if let Some(container) = container {
+            let container = container.bind(py);
+            if container.hasattr(intern!(py, "assign_parent"))? {
+                container.call_method1(intern!(py, "assign_parent"), (slf,))?;
+            }
+        }
+        Ok(())
+    }
+
+    #[getter]
+    fn related(slf: &Bound<'_, Self>) -> PyResult<Vec<PyObject>> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let base: &Provider = this.as_ref();
+        let mut related = providers_only(py, base.overridden.iter());
+        if let Some(container) = &this.container {
+            if let Ok(providers) = container.getattr(py, intern!(py, "providers")) {
+                for provider in providers.downcast_bound::<PyDict>(py)?.values() {
+                    if provider.is_instance_of::<Provider>() {
+                        related.push(provider.unbind());
+                    }
+                }
+            }
+        }
+        Ok(related)
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let py = slf.py();
+            let (container_cls, container, overriding_providers, parent) = {
+                let this = slf.borrow();
+                (
+                    this.container_cls.clone(),
+                    this.container.clone(),
+                    this.overriding_providers.clone(),
+                    this.parent.clone(),
+                )
+            };
+            let container = deepcopy_option(container, memo)?;
+            let overriding_providers =
+                deepcopy(overriding_providers.into_py(py).bind(py), memo)?.extract()?;
+            let parent = copy_parent(parent, memo)?;
+            let mut copied = copied.borrow_mut();
+            copied.container_cls = container_cls;
+            copied.container = container;
+            copied.overriding_providers = overriding_providers;
+            copied.parent = parent;
+            Ok(())
+        })
+    }
+}
+
+/// Resource provider provides a component with initialization and shutdown.
+///
+/// ``provides`` may be a generator function, an async generator function,
+/// a coroutine function or a plain callable. Code after the ``yield`` of a
+/// generator is run on ``shutdown()``.
+#[pyclass(extends=Provider, module="inj", subclass)]
+#[derive(Default)]
+pub struct Resource {
+    provides: Option<PyObject>,
+    injections: Injections,
+    initialized: bool,
+    resource: Option<PyObject>,
+    shutdowner: Option<PyObject>,
+}
+
+#[pymethods]
+impl Resource {
+    #[new]
+    #[pyo3(signature = (provides=None, *args, **kwargs))]
+    fn new(
+        provides: Option<PyObject>,
+        args: Vec<PyObject>,
+        kwargs: Option<IndexMap<String, PyObject>>,
+    ) -> (Self, Provider) {
+        let this = Self {
+            provides,
+            injections: Injections::new(args, kwargs),
+            ..Default::default()
+        };
+        (this, Provider::new())
+    }
+
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let (provides, injections) = {
+            let this = slf.borrow();
+            if this.initialized {
+                return Ok(this.resource.clone().unwrap_or_else(|| py.None()));
+            }
+            let Some(provides) = this.provides.clone() else {
+                return Err(errors::Error::new_err(format!(
+                    "Resource \"{}\" has no provides",
+                    provider_label(slf.as_any())?
+                )));
+            };
+            (provides, this.injections.clone())
+        };
+        let (args, kwargs) = injections.call_args(py, args, kwargs)?;
+        let provides = provides.bind(py);
+
+        let inspect = py.import_bound(intern!(py, "inspect"))?;
+        let is = |name: &Bound<'_, PyString>| -> PyResult<bool> {
+            inspect.call_method1(name, (provides,))?.is_truthy()
+        };
+        if is(intern!(py, "isgeneratorfunction"))? {
+            let generator = provides.call(args, Some(&kwargs))?;
+            let resource = generator.call_method0(intern!(py, "__next__"))?;
+            Self::set_initialized(slf, resource.clone().unbind(), Some(generator.unbind()));
+            Ok(resource.unbind())
+        } else if is(intern!(py, "isasyncgenfunction"))? {
+            let generator = provides.call(args, Some(&kwargs))?;
+            let first = generator.call_method0(intern!(py, "__anext__"))?;
+            Self::init_future(slf, &first, Some(generator.unbind()))
+        } else if is(intern!(py, "iscoroutinefunction"))? {
+            let coroutine = provides.call(args, Some(&kwargs))?;
+            Self::init_future(slf, &coroutine, None)
+        } else {
+            let resource = provides.call(args, Some(&kwargs))?;
+            Self::set_initialized(slf, resource.clone().unbind(), None);
+            Ok(resource.unbind())
+        }
+    }
+
+    /// Initialize resource.
+    fn init(slf: &Bound<'_, Self>) -> PyResult<PyObject> {
+        Ok(slf.call0()?.unbind())
+    }
+
+    /// Shutdown resource.
+    ///
+    /// Returns an awaitable for async generator resources.
+    fn shutdown(slf: &Bound<'_, Self>) -> PyResult<PyObject> {
+        let py = slf.py();
+        let shutdowner = {
+            let mut this = slf.borrow_mut();
+            if !this.initialized {
+                return Ok(py.None());
+            }
+            this.initialized = false;
+            this.resource = None;
+            this.shutdowner.take()
+        };
+        let Some(shutdowner) = shutdowner else {
+            return Ok(py.None());
+        };
+        let shutdowner = shutdowner.bind(py);
+
+        let is_async = py
+            .import_bound(intern!(py, "inspect"))?
+            .call_method1(intern!(py, "isasyncgen"), (shutdowner,))?
+            .is_truthy()?;
+        if is_async {
+            let next = shutdowner.call_method0(intern!(py, "__anext__"))?;
+            let awaitable = Awaitable::map(next.unbind(), |py, result| match result {
+                Err(err) if !err.is_instance_of::<PyStopAsyncIteration>(py) => Err(err),
+                _ => Ok(py.None()),
+            });
+            return Ok(Bound::new(py, awaitable)?.into_any().unbind());
+        }
+        match shutdowner.call_method0(intern!(py, "__next__")) {
+            Err(err) if !err.is_instance_of::<PyStopIteration>(py) => Err(err),
+            _ => Ok(py.None()),
+        }
+    }
+
+    /// Check if resource is initialized.
+    #[getter]
+    fn initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// Return provided callable.
+    #[getter]
+    fn provides(&self) -> Option<PyObject> {
+        self.provides.clone()
+    }
+
+    /// Return positional argument injections.
+    #[getter]
+    fn args(&self) -> Vec<PyObject> {
+        self.injections.args.clone()
+    }
+
+    /// Return keyword argument injections.
+    #[getter]
+    fn kwargs(&self) -> IndexMap<String, PyObject> {
+        self.injections.kwargs.clone()
+    }
+
+    /// Set provided callable.
+    fn set_provides<'py>(slf: &Bound<'py, Self>, provides: PyObject) -> Bound<'py, Self> {
+        slf.borrow_mut().provides = Some(provides);
+        slf.clone()
+    }
+
+    /// Add positional argument injections.
+    #[pyo3(signature = (*args))]
+    fn add_args<'py>(slf: &Bound<'py, Self>, args: Vec<PyObject>) -> Bound<'py, Self> {
+        slf.borrow_mut().injections.add_args(args);
+        slf.clone()
+    }
+
+    /// Add keyword argument injections.
+    #[pyo3(signature = (**kwargs))]
+    fn add_kwargs<'py>(
+        slf: &Bound<'py, Self>,
+        kwargs: Option<IndexMap<String, PyObject>>,
+    ) -> Bound<'py, Self> {
+        slf.borrow_mut().injections.add_kwargs(kwargs);
+        slf.clone()
+    }
+
+    #[getter]
+    fn related(slf: &Bound<'_, Self>) -> Vec<PyObject> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let base: &Provider = this.as_ref();
+        providers_only(
+            py,
+            this.provides
+                .iter()
+                .chain(this.injections.values())
+                .chain(base.overridden.iter()),
+        )
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let (provides, injections) = {
+                let this = slf.borrow();
+                (this.provides.clone(), this.injections.clone())
+            };
+            let provides = deepcopy_option(provides, memo)?;
+            let injections = injections.deepcopy(memo)?;
+            let mut copied = copied.borrow_mut();
+            copied.provides = provides;
+            copied.injections = injections;
+            Ok(())
+        })
+    }
+}
+
+impl Resource {
+    fn set_initialized(slf: &Bound<'_, Self>, resource: PyObject, shutdowner: Option<PyObject>) {
+        let mut this = slf.borrow_mut();
+        this.initialized = true;
+        this.resource = Some(resource);
+        this.shutdowner = shutdowner;
+    }
+
+    /// Schedule async initialization, sharing the future between callers.
+    ///
+    /// Failed initialization resets the resource, so it can be retried.
+    fn init_future(
+        slf: &Bound<'_, Self>,
+        awaitable: &Bound<'_, PyAny>,
+        shutdowner: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        // State is updated before the future is resolved, so it is
+        // consistent for whoever awaits the future
+        let resource = slf.clone().unbind();
+        let initialized = Awaitable::map(awaitable.clone().unbind(), move |py, result| {
+            let mut this = resource.bind(py).borrow_mut();
+            if result.is_ok() {
+                this.shutdowner = shutdowner;
+            } else {
+                this.initialized = false;
+                this.resource = None;
+                this.shutdowner = None;
+            }
+            result
+        });
+        let future = py
+            .import_bound(intern!(py, "asyncio"))?
+            .call_method1(intern!(py, "ensure_future"), (initialized,))?;
+        Self::set_initialized(slf, future.clone().unbind(), None);
+        Ok(future.unbind())
+    }
+}
+
+/// Callable provider calls wrapped callable on every call.
+///
+/// Provider injections are called on every call, call arguments are merged
+/// with injected ones.
+#[pyclass(extends=Provider, module="inj", subclass)]
+#[derive(Default)]
+pub struct Callable {
+    provides: Option<PyObject>,
+    injections: Injections,
+}
+
+#[pymethods]
+impl Callable {
+    #[new]
+    #[pyo3(signature = (provides=None, *args, **kwargs))]
+    fn new(
+        provides: Option<PyObject>,
+        args: Vec<PyObject>,
+        kwargs: Option<IndexMap<String, PyObject>>,
+    ) -> (Self, Provider) {
+        let this = Self {
+            provides,
+            injections: Injections::new(args, kwargs),
+        };
+        (this, Provider::new())
+    }
+
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let (provides, args, kwargs) = Self::call_args(slf, args, kwargs)?;
+        call_provides(slf.as_any(), provides, &args, &kwargs)
+    }
+
+    /// Return provided callable.
+    #[getter]
+    fn provides(&self) -> Option<PyObject> {
+        self.provides.clone()
+    }
+
+    /// Return positional argument injections.
+    #[getter]
+    fn args(&self) -> Vec<PyObject> {
+        self.injections.args.clone()
+    }
+
+    /// Return keyword argument injections.
+    #[getter]
+    fn kwargs(&self) -> IndexMap<String, PyObject> {
+        self.injections.kwargs.clone()
+    }
+
+    /// Set provided callable.
+    fn set_provides<'py>(slf: &Bound<'py, Self>, provides: PyObject) -> Bound<'py, Self> {
+        slf.borrow_mut().provides = Some(provides);
+        slf.clone()
+    }
+
+    /// Add positional argument injections.
+    #[pyo3(signature = (*args))]
+    fn add_args<'py>(slf: &Bound<'py, Self>, args: Vec<PyObject>) -> Bound<'py, Self> {
+        slf.borrow_mut().injections.add_args(args);
+        slf.clone()
+    }
+
+    /// Add keyword argument injections.
+    #[pyo3(signature = (**kwargs))]
+    fn add_kwargs<'py>(
+        slf: &Bound<'py, Self>,
+        kwargs: Option<IndexMap<String, PyObject>>,
+    ) -> Bound<'py, Self> {
+        slf.borrow_mut().injections.add_kwargs(kwargs);
+        slf.clone()
+    }
+
+    #[getter]
+    fn related(slf: &Bound<'_, Self>) -> Vec<PyObject> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let base: &Provider = this.as_ref();
+        providers_only(
+            py,
+            this.provides
+                .iter()
+                .chain(this.injections.values())
+                .chain(base.overridden.iter()),
+        )
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let (provides, injections) = {
+                let this = slf.borrow();
+                (this.provides.clone(), this.injections.clone())
+            };
+            let provides = deepcopy_option(provides, memo)?;
+            let injections = injections.deepcopy(memo)?;
+            let mut copied = copied.borrow_mut();
+            copied.provides = provides;
+            copied.injections = injections;
+            Ok(())
+        })
+    }
+}
+
+impl Callable {
+    /// Return provided callable with resolved call arguments
+    fn call_args<'py>(
+        slf: &Bound<'py, Self>,
+        args: &Bound<'py, PyTuple>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<(PyObject, Bound<'py, PyTuple>, Bound<'py, PyDict>)> {
+        let py = slf.py();
+        let (provides, injections) = {
+            let this = slf.borrow();
+            let Some(provides) = this.provides.clone() else {
+                return Err(errors::Error::new_err(format!(
+                    "Provider \"{}\" has no provides",
+                    provider_label(slf.as_any())?
+                )));
+            };
+            (provides, this.injections.clone())
+        };
+        let (args, kwargs) = injections.call_args(py, args, kwargs)?;
+        Ok((provides, args, kwargs))
+    }
+}
+
+/// Coroutine provider calls wrapped coroutine function on every call.
+///
+/// Returns an awaitable. Awaitable injections, e.g. async resources, are
+/// awaited before the coroutine function is called.
+#[pyclass(extends=Callable, module="inj", subclass)]
+pub struct Coroutine;
+
+#[pymethods]
+impl Coroutine {
+    #[new]
+    #[pyo3(signature = (provides=None, *args, **kwargs))]
+    fn new(
+        py: Python,
+        provides: Option<PyObject>,
+        args: Vec<PyObject>,
+        kwargs: Option<IndexMap<String, PyObject>>,
+    ) -> PyResult<PyClassInitializer<Self>> {
+        if let Some(provides) = &provides {
+            check_coroutine_function(provides.bind(py))?;
+        }
+        Ok(PyClassInitializer::from(Callable::new(provides, args, kwargs)).add_subclass(Self))
+    }
+
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let (provides, args, kwargs) = Callable::call_args(slf.downcast()?, args, kwargs)?;
+        AsyncCall::new(provides, &args, &kwargs, true).call(py)
+    }
+
+    /// Set provided coroutine function.
+    fn set_provides<'py>(
+        slf: &Bound<'py, Self>,
+        provides: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, Self>> {
+        check_coroutine_function(provides)?;
+        slf.borrow_mut().as_mut().provides = Some(provides.clone().unbind());
+        Ok(slf.clone())
+    }
+}
+
+fn check_coroutine_function(provides: &Bound<'_, PyAny>) -> PyResult<()> {
+    let py = provides.py();
+    let is_coroutine_function = py
+        .import_bound(intern!(py, "asyncio"))?
+        .call_method1(intern!(py, "iscoroutinefunction"), (provides,))?
+        .is_truthy()?;
+    if !is_coroutine_function {
+        return Err(errors::Error::new_err(format!(
+            "Provider Coroutine expects a coroutine function, got {}",
+            provides.repr()?
+        )));
+    }
+    Ok(())
+}
+
+/// Argument of `AsyncCall`, which is awaited before the call
+enum ArgumentSlot {
+    Positional(usize),
+    Keyword(PyObject),
+}
+
+/// Call awaiting awaitable arguments concurrently first.
+///
+/// Result of the call is awaited too when `await_result` is set.
+pub(crate) struct AsyncCall {
+    provides: PyObject,
+    args: Py<PyTuple>,
+    kwargs: Py<PyDict>,
+    slots: Vec<ArgumentSlot>,
+    awaiting: Vec<PyObject>,
+    await_result: bool,
+    called: bool,
+}
+
+impl AsyncCall {
+    pub(crate) fn new(
+        provides: PyObject,
+        args: &Bound<'_, PyTuple>,
+        kwargs: &Bound<'_, PyDict>,
+        await_result: bool,
+    ) -> Self {
+        let (mut slots, mut awaiting) = (Vec::new(), Vec::new());
+        for (position, arg) in args.iter().enumerate() {
+            if is_awaitable(&arg) {
+                slots.push(ArgumentSlot::Positional(position));
+                awaiting.push(arg.unbind());
+            }
+        }
+        for (name, value) in kwargs.iter() {
+            if is_awaitable(&value) {
+                slots.push(ArgumentSlot::Keyword(name.unbind()));
+                awaiting.push(value.unbind());
+            }
+        }
+        Self {
+            provides,
+            args: args.clone().unbind(),
+            kwargs: kwargs.clone().unbind(),
+            slots,
+            awaiting,
+            await_result,
+            called: false,
+        }
+    }
+
+    /// Call right away if there is nothing to await, otherwise return
+    /// an awaitable of the call.
+    pub(crate) fn call(self, py: Python) -> PyResult<PyObject> {
+        if self.slots.is_empty() {
+            return self
+                .provides
+                .call_bound(py, self.args.bind(py), Some(self.kwargs.bind(py)));
+        }
+        Ok(Bound::new(py, Awaitable::new(self))?.into_any().unbind())
+    }
+
+    fn substitute(&mut self, py: Python, values: Vec<PyObject>) -> PyResult<()> {
+        let mut args: Vec<PyObject> = self.args.bind(py).extract()?;
+        for (slot, value) in self.slots.drain(..).zip(values) {
+            match slot {
+                ArgumentSlot::Positional(position) => args[position] = value,
+                ArgumentSlot::Keyword(name) => self.kwargs.bind(py).set_item(name, value)?,
+            }
+        }
+        self.args = PyTuple::new_bound(py, args).unbind();
+        Ok(())
+    }
+}
+
+impl Resume for AsyncCall {
+    fn resume(&mut self, py: Python<'_>, value: PyResult<PyObject>) -> PyResult<Step> {
+        let value = value?;
+        if self.called {
+            return Ok(Step::Return(value));
+        }
+        match self.awaiting.len() {
+            0 => {}
+            1 => return Ok(Step::Await(self.awaiting.remove(0))),
+            _ => {
+                let gather = gather(self.awaiting.drain(..).collect());
+                return Ok(Step::Await(Bound::new(py, gather)?.into_any().unbind()));
+            }
+        }
+        let values = match self.slots.len() {
+            1 => vec![value],
+            _ => value.extract(py)?,
+        };
+        self.substitute(py, values)?;
+        self.called = true;
+        let result =
+            self.provides
+                .call_bound(py, self.args.bind(py), Some(self.kwargs.bind(py)))?;
+        if self.await_result && is_awaitable(result.bind(py)) {
+            Ok(Step::Await(result))
+        } else {
+            Ok(Step::Return(result))
+        }
+    }
+}
+
+/// Call provided callable, awaiting awaitable injections concurrently
+/// unless async mode of the provider is disabled.
+fn call_provides(
+    provider: &Bound<'_, PyAny>,
+    provides: PyObject,
+    args: &Bound<'_, PyTuple>,
+    kwargs: &Bound<'_, PyDict>,
+) -> PyResult<PyObject> {
+    let py = provider.py();
+    if Provider::awaits_injections(provider) {
+        AsyncCall::new(provides, args, kwargs, false).call(py)
+    } else {
+        provides.call_bound(py, args, Some(kwargs))
+    }
+}
+
+/// Factory provider creates a new object on every call.
+///
+/// ```python
+/// service = Factory(Service, database, timeout=10)
+/// ```
+#[pyclass(extends=Provider, module="inj", subclass)]
+#[derive(Default)]
+pub struct Factory {
+    provides: Option<PyObject>,
+    injections: Injections,
+    /// Call plan compiled from injections, dropped when they change
+    plan: Option<Arc<CallPlan>>,
+}
+
+#[pymethods]
+impl Factory {
+    #[new]
+    #[pyo3(signature = (provides=None, *args, **kwargs))]
+    fn new(
+        provides: Option<PyObject>,
+        args: Vec<PyObject>,
+        kwargs: Option<IndexMap<String, PyObject>>,
+    ) -> (Self, Provider) {
+        let this = Self {
+            provides,
+            injections: Injections::new(args, kwargs),
+            plan: None,
+        };
+        (this, Provider::new())
+    }
+
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        Self::create(slf, args, kwargs)
+    }
+
+    /// Return provided type.
+    #[getter]
+    fn provides(&self) -> Option<PyObject> {
+        self.provides.clone()
+    }
+
+    /// Return positional argument injections.
+    #[getter]
+    fn args(&self) -> Vec<PyObject> {
+        self.injections.args.clone()
+    }
+
+    /// Return keyword argument injections.
+    #[getter]
+    fn kwargs(&self) -> IndexMap<String, PyObject> {
+        self.injections.kwargs.clone()
+    }
+
+    /// Set provided type.
+    fn set_provides<'py>(slf: &Bound<'py, Self>, provides: PyObject) -> Bound<'py, Self> {
+        slf.borrow_mut().provides = Some(provides);
+        slf.clone()
+    }
+
+    /// Add positional argument injections.
+    #[pyo3(signature = (*args))]
+    fn add_args<'py>(slf: &Bound<'py, Self>, args: Vec<PyObject>) -> Bound<'py, Self> {
+        let mut this = slf.borrow_mut();
+        this.injections.add_args(args);
+        this.plan = None;
+        slf.clone()
+    }
+
+    /// Set positional argument injections, dropping previous ones.
+    #[pyo3(signature = (*args))]
+    fn set_args<'py>(slf: &Bound<'py, Self>, args: Vec<PyObject>) -> Bound<'py, Self> {
+        let mut this = slf.borrow_mut();
+        this.injections.set_args(args);
+        this.plan = None;
+        slf.clone()
+    }
+
+    /// Drop positional argument injections.
+    fn clear_args<'py>(slf: &Bound<'py, Self>) -> Bound<'py, Self> {
+        let mut this = slf.borrow_mut();
+        this.injections.clear_args();
+        this.plan = None;
+        slf.clone()
+    }
+
+    /// Add keyword argument injections.
+    #[pyo3(signature = (**kwargs))]
+    fn add_kwargs<'py>(
+        slf: &Bound<'py, Self>,
+        kwargs: Option<IndexMap<String, PyObject>>,
+    ) -> Bound<'py, Self> {
+        let mut this = slf.borrow_mut();
+        this.injections.add_kwargs(kwargs);
+        this.plan = None;
+        slf.clone()
+    }
+
+    /// Set keyword argument injections, dropping previous ones.
+    #[pyo3(signature = (**kwargs))]
+    fn set_kwargs<'py>(
+        slf: &Bound<'py, Self>,
+        kwargs: Option<IndexMap<String, PyObject>>,
+    ) -> Bound<'py, Self> {
+        let mut this = slf.borrow_mut();
+        this.injections.set_kwargs(kwargs);
+        this.plan = None;
+        slf.clone()
+    }
+
+    /// Drop keyword argument injections.
+    fn clear_kwargs<'py>(slf: &Bound<'py, Self>) -> Bound<'py, Self> {
+        let mut this = slf.borrow_mut();
+        this.injections.clear_kwargs();
+        this.plan = None;
+        slf.clone()
+    }
+
+    #[getter]
+    fn related(slf: &Bound<'_, Self>) -> Vec<PyObject> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let base: &Provider = this.as_ref();
+        providers_only(
+            py,
+            this.provides
+                .iter()
+                .chain(this.injections.values())
+                .chain(base.overridden.iter()),
+        )
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let (provides, injections) = {
+                let this = slf.borrow();
+                (this.provides.clone(), this.injections.clone())
+            };
+            let provides = deepcopy_option(provides, memo)?;
+            let injections = injections.deepcopy(memo)?;
+            let mut copied = copied.borrow_mut();
+            copied.provides = provides;
+            copied.injections = injections;
+            copied.plan = None;
+            Ok(())
+        })
+    }
+}
+
+impl Factory {
+    /// Create new object with resolved injections
+    fn create(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let provides = slf.borrow().provides.clone();
+        let Some(provides) = provides else {
+            return Err(errors::Error::new_err(format!(
+                "Provider \"{}\" has no provides",
+                provider_label(slf.as_any())?
+            )));
+        };
+        let plan = {
+            let mut this = slf.borrow_mut();
+            let this = &mut *this;
+            this.plan
+                .get_or_insert_with(|| Arc::new(CallPlan::compile(py, &this.injections)))
+                .clone()
+        };
+        plan.call(slf.as_any(), provides.bind(py), args, kwargs)
+    }
+
+    /// Drop call plan of the factory, if provider is one
+    fn invalidate_plan(provider: &Bound<'_, PyAny>) {
+        if let Ok(factory) = provider.downcast::<Self>() {
+            if let Ok(mut factory) = factory.try_borrow_mut() {
+                factory.plan = None;
+            }
+        }
+    }
+}
+
+/// Injection of the call plan
+enum Injection {
+    Provider(PyObject),
+    Value(PyObject),
+}
+
+impl Injection {
+    fn new(injection: &Bound<'_, PyAny>) -> Self {
+        if injection.is_instance_of::<Provider>() {
+            Self::Provider(injection.clone().unbind())
+        } else {
+            Self::Value(injection.clone().unbind())
+        }
+    }
+
+    fn resolve<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        match self {
+            Self::Provider(provider) => provider.bind(py).call0(),
+            Self::Value(value) => Ok(value.bind(py).clone()),
+        }
+    }
+}
+
+/// Factory call compiled from its injections.
+///
+/// Providers are told apart from literal values once and keyword names are
+/// interned, so a call only resolves injections and calls the provided
+/// callable with vectorcall.
+struct CallPlan {
+    args: Vec<Injection>,
+    kwargs: Vec<Injection>,
+    kwnames: Py<PyTuple>,
+}
+
+impl CallPlan {
+    fn compile(py: Python, injections: &Injections) -> Self {
+        let Injections { args, kwargs } = injections;
         Self {
-            overridden: Vec::new(),
-            last_overriding: None,
-            overrides: Vec::new(),
-            // async_mode: AsyncMode::Undefined,
+            args: args
+                .iter()
+                .map(|arg| Injection::new(arg.bind(py)))
+                .collect(),
+            kwargs: kwargs
+                .values()
+                .map(|value| Injection::new(value.bind(py)))
+                .collect(),
+            kwnames: PyTuple::new_bound(
+                py,
+                kwargs.keys().map(|name| PyString::intern_bound(py, name)),
+            )
+            .unbind(),
+        }
+    }
+
+    /// Call provided callable with resolved injections and call arguments.
+    ///
+    /// Keyword arguments of the call take precedence over injected ones,
+    /// which are not resolved then.
+    fn call(
+        &self,
+        provider: &Bound<'_, PyAny>,
+        provides: &Bound<'_, PyAny>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = provider.py();
+        let mut values = Vec::with_capacity(self.args.len() + args.len() + self.kwargs.len());
+        for injection in &self.args {
+            values.push(injection.resolve(py)?);
+        }
+        values.extend(args.iter());
+        let nargs = values.len();
+
+        let kwnames = match kwargs.filter(|kwargs| !kwargs.is_empty()) {
+            None => {
+                for injection in &self.kwargs {
+                    values.push(injection.resolve(py)?);
+                }
+                self.kwnames.bind(py).clone()
+            }
+            Some(kwargs) => {
+                let mut names = Vec::with_capacity(self.kwargs.len() + kwargs.len());
+                for (name, injection) in self.kwnames.bind(py).iter().zip(&self.kwargs) {
+                    if !kwargs.contains(&name)? {
+                        values.push(injection.resolve(py)?);
+                        names.push(name);
+                    }
+                }
+                for (name, value) in kwargs.iter() {
+                    names.push(name);
+                    values.push(value);
+                }
+                PyTuple::new_bound(py, names)
+            }
+        };
+
+        if Provider::awaits_injections(provider) && values.iter().any(is_awaitable) {
+            let call_kwargs = PyDict::new_bound(py);
+            for (name, value) in kwnames.iter().zip(&values[nargs..]) {
+                call_kwargs.set_item(name, value)?;
+            }
+            let call_args = PyTuple::new_bound(py, &values[..nargs]);
+            return AsyncCall::new(provides.clone().unbind(), &call_args, &call_kwargs, false)
+                .call(py);
+        }
+
+        let pointers: Vec<*mut ffi::PyObject> = values.iter().map(Bound::as_ptr).collect();
+        let kwnames = match kwnames.is_empty() {
+            true => std::ptr::null_mut(),
+            false => kwnames.as_ptr(),
+        };
+        // SAFETY: pointers are borrowed from `values` and `kwnames`, which
+        // outlive the call, and the number of keyword values matches names.
+        unsafe {
+            let result =
+                ffi::PyObject_Vectorcall(provides.as_ptr(), pointers.as_ptr(), nargs, kwnames);
+            Ok(Bound::from_owned_ptr_or_err(py, result)?.unbind())
+        }
+    }
+}
+
+/// Singleton provider creates an object on the first call and returns the
+/// same object afterwards.
+#[pyclass(extends=Factory, module="inj", subclass)]
+#[derive(Default)]
+pub struct Singleton {
+    instance: Option<PyObject>,
+    /// Concurrent awaits of async initialization in progress
+    pending: Option<Waiters>,
+    /// Bumped on reset, so initialization started before it is discarded
+    generation: u64,
+}
+
+#[pymethods]
+impl Singleton {
+    #[new]
+    #[pyo3(signature = (provides=None, *args, **kwargs))]
+    fn new(
+        provides: Option<PyObject>,
+        args: Vec<PyObject>,
+        kwargs: Option<IndexMap<String, PyObject>>,
+    ) -> PyClassInitializer<Self> {
+        PyClassInitializer::from(Factory::new(provides, args, kwargs)).add_subclass(Self::default())
+    }
+
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        {
+            let this = slf.borrow();
+            if let Some(instance) = &this.instance {
+                tracing::mark_cached();
+                return Ok(instance.clone_ref(py));
+            }
+            if this.pending.is_some() {
+                return Self::wait(slf, args, kwargs);
+            }
+        }
+        let instance = Factory::create(slf.downcast()?, args, kwargs)?;
+        if !is_awaitable(instance.bind(py)) {
+            slf.borrow_mut().instance = Some(instance.clone_ref(py));
+            return Ok(instance);
+        }
+
+        let generation = {
+            let mut this = slf.borrow_mut();
+            this.pending = Some(Waiters::default());
+            this.generation
+        };
+        let initialization = SingletonInit {
+            singleton: slf.clone().unbind(),
+            awaitable: Some(instance),
+            generation,
+            finished: false,
+        };
+        Ok(Bound::new(py, Awaitable::new(initialization))?
+            .into_any()
+            .unbind())
+    }
+
+    /// Reset cached instance, next call creates a new one.
+    ///
+    /// Calls waiting for async initialization in progress get an error.
+    fn reset(slf: &Bound<'_, Self>) -> PyResult<()> {
+        let py = slf.py();
+        let waiters = {
+            let mut this = slf.borrow_mut();
+            this.instance = None;
+            this.generation += 1;
+            this.pending.take()
+        };
+        match waiters {
+            Some(waiters) => {
+                let err = errors::Error::new_err(format!(
+                    "Singleton \"{}\" was reset during initialization",
+                    provider_label(slf.as_any())?
+                ));
+                waiters.wake(py, &Err(err))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Singleton {
+    /// Wait for async initialization started by another call.
+    ///
+    /// Waiters registered while initialization is in progress get its
+    /// result or exception. Initialization is started again by a call made
+    /// after a failed one has finished.
+    fn wait(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let singleton = slf.clone().unbind();
+        let (args, kwargs) = (
+            args.clone().unbind(),
+            kwargs.map(|kwargs| kwargs.clone().unbind()),
+        );
+        let mut waiting = false;
+        let awaitable = Awaitable::new(move |py: Python<'_>, value: PyResult<PyObject>| {
+            if waiting {
+                return Ok(Step::Return(value?));
+            }
+            waiting = true;
+            let singleton = singleton.bind(py);
+            let mut this = singleton.borrow_mut();
+            if let Some(instance) = &this.instance {
+                return Ok(Step::Return(instance.clone_ref(py)));
+            }
+            if let Some(waiters) = this.pending.as_mut() {
+                return Ok(Step::Await(waiters.wait(py)?));
+            }
+            drop(this);
+            let kwargs = kwargs.as_ref().map(|kwargs| kwargs.clone_ref(py));
+            let result =
+                singleton.call_method1(intern!(py, "_provide"), (args.clone_ref(py), kwargs))?;
+            if is_awaitable(&result) {
+                Ok(Step::Await(result.unbind()))
+            } else {
+                Ok(Step::Return(result.unbind()))
+            }
+        });
+        Ok(Bound::new(py, awaitable)?.into_any().unbind())
+    }
+}
+
+/// Async initialization of a singleton, sharing its outcome with waiters.
+///
+/// Initialization closed or dropped before it has finished is abandoned:
+/// waiters get an error and the next call starts initialization again.
+struct SingletonInit {
+    singleton: Py<Singleton>,
+    awaitable: Option<PyObject>,
+    generation: u64,
+    finished: bool,
+}
+
+impl SingletonInit {
+    /// Store instance and wake waiters, unless singleton was reset meanwhile
+    fn finish(&mut self, py: Python<'_>, result: &PyResult<PyObject>) -> PyResult<()> {
+        self.finished = true;
+        let waiters = {
+            let Ok(mut this) = self.singleton.bind(py).try_borrow_mut() else {
+                return Ok(());
+            };
+            if this.generation != self.generation {
+                return Ok(());
+            }
+            if let Ok(instance) = result {
+                this.instance = Some(instance.clone_ref(py));
+            }
+            this.pending.take()
+        };
+        match waiters {
+            Some(waiters) => waiters.wake(py, result),
+            None => Ok(()),
+        }
+    }
+
+    fn abandon(&mut self, py: Python<'_>) {
+        if self.finished {
+            return;
+        }
+        let singleton = self.singleton.clone_ref(py).into_bound(py).into_any();
+        let label = provider_label(&singleton).unwrap_or_default();
+        let err = errors::Error::new_err(format!(
+            "Initialization of singleton \"{}\" was abandoned",
+            label
+        ));
+        if let Err(err) = self.finish(py, &Err(err)) {
+            err.write_unraisable_bound(py, Some(&singleton));
+        }
+        // Initialization, which has not been started, is never awaited
+        if let Some(awaitable) = self.awaitable.take() {
+            let awaitable = awaitable.bind(py);
+            if awaitable.hasattr(intern!(py, "close")).unwrap_or(false) {
+                if let Err(err) = awaitable.call_method0(intern!(py, "close")) {
+                    err.write_unraisable_bound(py, Some(awaitable));
+                }
+            }
+        }
+    }
+}
+
+impl Resume for SingletonInit {
+    fn resume(&mut self, py: Python<'_>, value: PyResult<PyObject>) -> PyResult<Step> {
+        if let Some(awaitable) = self.awaitable.take() {
+            return Ok(Step::Await(awaitable));
+        }
+        self.finish(py, &value)?;
+        Ok(Step::Return(value?))
+    }
+
+    fn close(&mut self, py: Python<'_>) {
+        self.abandon(py);
+    }
+}
+
+impl Drop for SingletonInit {
+    fn drop(&mut self) {
+        if !self.finished {
+            Python::with_gil(|py| self.abandon(py));
+        }
+    }
+}
+
+/// Scoped provider creates one object per entered scope of the name.
+///
+/// ```python
+/// session = Scoped("request", Session, engine)
+///
+/// with container.scope("request"):
+///     assert container.session() is container.session()
+/// ```
+///
+/// Generator functions are run to the first `yield`, the rest of the
+/// generator is run on scope exit.
+#[pyclass(extends=Factory, module="inj", subclass)]
+#[derive(Default)]
+pub struct Scoped {
+    scope: Option<String>,
+}
+
+#[pymethods]
+impl Scoped {
+    #[new]
+    #[pyo3(signature = (scope=None, provides=None, *args, **kwargs))]
+    fn new(
+        scope: Option<String>,
+        provides: Option<PyObject>,
+        args: Vec<PyObject>,
+        kwargs: Option<IndexMap<String, PyObject>>,
+    ) -> PyClassInitializer<Self> {
+        PyClassInitializer::from(Factory::new(provides, args, kwargs)).add_subclass(Self { scope })
+    }
+
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let Some(name) = slf.borrow().scope.clone() else {
+            return Err(errors::Error::new_err(format!(
+                "Provider \"{}\" has no scope",
+                provider_label(slf.as_any())?
+            )));
+        };
+        let not_entered = || -> PyResult<PyErr> {
+            let error = core::Error::ScopeNotEntered {
+                name: provider_label(slf.as_any())?,
+                scope: name.clone(),
+            };
+            Ok(errors::Error::new_err(error.to_string()))
+        };
+        let Some(current) = Scope::current(py)? else {
+            return Err(not_entered()?);
+        };
+        let Some(owner) = Scope::find(&current, &name) else {
+            return Err(not_entered()?);
+        };
+        if let Some(instance) = Scope::get(&owner, slf.as_any())? {
+            tracing::mark_cached();
+            return Ok(instance);
+        }
+
+        let instance = Factory::create(slf.downcast()?, args, kwargs)?.into_bound(py);
+        if scopes::is_generator(&instance)? {
+            let value = instance.call_method0(intern!(py, "__next__"))?;
+            Scope::add_resource(&owner, &instance);
+            Scope::store(&owner, slf.as_any(), &value)?;
+            return Ok(value.unbind());
+        }
+        let awaitable = if scopes::is_async_generator(&instance)? {
+            Scope::add_resource(&owner, &instance);
+            instance.call_method0(intern!(py, "__anext__"))?
+        } else if is_awaitable(&instance) {
+            instance
+        } else {
+            Scope::store(&owner, slf.as_any(), &instance)?;
+            return Ok(instance.unbind());
+        };
+        let (owner, provider) = (owner.unbind(), slf.clone().into_any().unbind());
+        let awaitable = Awaitable::map(awaitable.unbind(), move |py, value| {
+            let value = value?;
+            Scope::store(owner.bind(py), provider.bind(py), value.bind(py))?;
+            Ok(value)
+        });
+        Ok(Bound::new(py, awaitable)?.into_any().unbind())
+    }
+
+    /// Return scope name.
+    #[getter]
+    fn scope(&self) -> Option<String> {
+        self.scope.clone()
+    }
+
+    /// Set scope name.
+    fn set_scope<'py>(slf: &Bound<'py, Self>, scope: String) -> Bound<'py, Self> {
+        slf.borrow_mut().scope = Some(scope);
+        slf.clone()
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        if let Some(copied) = memo_get(slf.as_any(), memo)? {
+            return Ok(copied);
+        }
+        let copied = Factory::__deepcopy__(slf.downcast()?, memo)?;
+        copied.downcast::<Self>()?.borrow_mut().scope = slf.borrow().scope.clone();
+        Ok(copied)
+    }
+}
+
+/// Check that abstract provider is overridden by a provider of a subtype.
+fn check_abstract_override<T: pyo3::PyTypeInfo>(
+    slf: &Bound<'_, Factory>,
+    provider: &Bound<'_, PyAny>,
+) -> PyResult<()> {
+    let py = slf.py();
+    if !provider.is_instance_of::<T>() {
+        return Err(errors::Error::new_err(format!(
+            "Provider \"{}\" can be overridden only by {} providers, got {}",
+            provider_label(slf.as_any())?,
+            T::NAME,
+            provider.repr()?
+        )));
+    }
+    let base = slf.borrow().provides.clone();
+    let provides = provider.downcast::<Factory>()?.borrow().provides.clone();
+    if let (Some(base), Some(provides)) = (base, provides) {
+        let (base, provides) = (base.bind(py), provides.bind(py));
+        let is_subclass = match (base.downcast::<PyType>(), provides.downcast::<PyType>()) {
+            (Ok(base), Ok(provides)) => provides.is_subclass(base)?,
+            _ => false,
+        };
+        if !is_subclass {
+            return Err(errors::Error::new_err(format!(
+                "Provider \"{}\" expected to get a subclass of {}, got {}",
+                provider_label(slf.as_any())?,
+                base.repr()?,
+                provides.repr()?
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn abstract_call_error(slf: &Bound<'_, PyAny>) -> PyResult<PyErr> {
+    Ok(errors::Error::new_err(format!(
+        "Provider \"{}\" must be overridden before calling",
+        provider_label(slf)?
+    )))
+}
+
+/// Abstract factory must be overridden by a factory of a subclass of the
+/// provided type before it can be called.
+///
+/// ```python
+/// cache = AbstractFactory(Cache)
+/// cache.override(Factory(RedisCache, host="localhost"))
+/// ```
+#[pyclass(extends=Factory, module="inj", subclass)]
+pub struct AbstractFactory;
+
+#[pymethods]
+impl AbstractFactory {
+    #[new]
+    #[pyo3(signature = (provides=None, *args, **kwargs))]
+    fn new(
+        provides: Option<PyObject>,
+        args: Vec<PyObject>,
+        kwargs: Option<IndexMap<String, PyObject>>,
+    ) -> PyClassInitializer<Self> {
+        PyClassInitializer::from(Factory::new(provides, args, kwargs)).add_subclass(Self)
+    }
+
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        _args: &Bound<'_, PyTuple>,
+        _kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        Err(abstract_call_error(slf.as_any())?)
+    }
+
+    /// Override provider with a factory of a subclass.
+    fn r#override(
+        slf: &Bound<'_, Self>,
+        provider: &Bound<'_, PyAny>,
+    ) -> PyResult<OverridingContext> {
+        check_abstract_override::<Factory>(slf.downcast()?, provider)?;
+        Provider::r#override(slf.downcast()?, provider)
+    }
+}
+
+/// Abstract singleton must be overridden by a singleton of a subclass of
+/// the provided type before it can be called.
+#[pyclass(extends=Singleton, module="inj", subclass)]
+pub struct AbstractSingleton;
+
+#[pymethods]
+impl AbstractSingleton {
+    #[new]
+    #[pyo3(signature = (provides=None, *args, **kwargs))]
+    fn new(
+        provides: Option<PyObject>,
+        args: Vec<PyObject>,
+        kwargs: Option<IndexMap<String, PyObject>>,
+    ) -> PyClassInitializer<Self> {
+        Singleton::new(provides, args, kwargs).add_subclass(Self)
+    }
+
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        _args: &Bound<'_, PyTuple>,
+        _kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        Err(abstract_call_error(slf.as_any())?)
+    }
+
+    /// Override provider with a singleton of a subclass.
+    fn r#override(
+        slf: &Bound<'_, Self>,
+        provider: &Bound<'_, PyAny>,
+    ) -> PyResult<OverridingContext> {
+        check_abstract_override::<Singleton>(slf.downcast()?, provider)?;
+        Provider::r#override(slf.downcast()?, provider)
+    }
+}
+
+/// Object provider returns provided object "as is".
+#[pyclass(extends=Provider, module="inj", subclass)]
+#[derive(Default)]
+pub struct Object {
+    provides: Option<PyObject>,
+}
+
+#[pymethods]
+impl Object {
+    #[new]
+    #[pyo3(signature = (provides=None))]
+    fn new(provides: Option<PyObject>) -> (Self, Provider) {
+        (Self { provides }, Provider::new())
+    }
+
+    fn _provide(
+        &self,
+        py: Python,
+        _args: &Bound<'_, PyTuple>,
+        _kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyObject {
+        match &self.provides {
+            Some(provides) => provides.clone_ref(py),
+            None => py.None(),
+        }
+    }
+
+    /// Return provided object.
+    #[getter]
+    fn provides(&self) -> Option<PyObject> {
+        self.provides.clone()
+    }
+
+    /// Set provided object.
+    fn set_provides<'py>(slf: &Bound<'py, Self>, provides: PyObject) -> Bound<'py, Self> {
+        slf.borrow_mut().provides = Some(provides);
+        slf.clone()
+    }
+
+    #[getter]
+    fn related(slf: &Bound<'_, Self>) -> Vec<PyObject> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let base: &Provider = this.as_ref();
+        providers_only(py, this.provides.iter().chain(base.overridden.iter()))
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let provides = slf.borrow().provides.clone();
+            let provides = deepcopy_option(provides, memo)?;
+            copied.borrow_mut().provides = provides;
+            Ok(())
+        })
+    }
+}
+
+/// Self provider returns the container it belongs to.
+///
+/// Declarative container has a single ``Self`` provider available as
+/// ``__self__`` and under every name it is declared with, its alternative
+/// names.
+#[pyclass(extends=Provider, module="inj", subclass)]
+#[derive(Default)]
+pub struct SelfProvider {
+    pub(crate) container: Option<PyObject>,
+    alt_names: Vec<String>,
+}
+
+#[pymethods]
+impl SelfProvider {
+    #[new]
+    #[pyo3(signature = (container=None))]
+    fn new(container: Option<PyObject>) -> (Self, Provider) {
+        let this = Self {
+            container,
+            alt_names: Vec::new(),
+        };
+        (this, Provider::new())
+    }
+
+    fn _provide(
+        &self,
+        py: Python,
+        _args: &Bound<'_, PyTuple>,
+        _kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyObject {
+        match &self.container {
+            Some(container) => container.clone_ref(py),
+            None => py.None(),
         }
     }
+
+    /// Return container.
+    #[getter]
+    fn container(&self) -> Option<PyObject> {
+        self.container.clone()
+    }
+
+    /// Return alternative names.
+    #[getter]
+    pub fn alt_names(&self) -> Vec<String> {
+        self.alt_names.clone()
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let (container, alt_names) = {
+                let this = slf.borrow();
+                (this.container.clone(), this.alt_names.clone())
+            };
+            let container = copy_parent(container, memo)?;
+            let mut copied = copied.borrow_mut();
+            copied.container = container;
+            copied.alt_names = alt_names;
+            Ok(())
+        })
+    }
+}
+
+impl SelfProvider {
+    pub(crate) fn initializer() -> PyClassInitializer<Self> {
+        PyClassInitializer::from(Provider::new()).add_subclass(Self::default())
+    }
+
+    /// Set container.
+    pub(crate) fn set_container(&mut self, container: PyObject) {
+        self.container = Some(container);
+    }
+
+    /// Set alternative names.
+    pub(crate) fn set_alt_names(&mut self, alt_names: Vec<String>) {
+        self.alt_names = alt_names;
+    }
+}
+
+/// Register `DependenciesContainer` provider.
+///
+/// pyo3 does not fill the `tp_init` slot from `__init__`, setting the
+/// attribute again makes the interpreter do it.
+pub fn add_dependencies_container(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    let cls = py.get_type_bound::<DependenciesContainer>();
+    py.get_type_bound::<PyType>()
+        .getattr(intern!(py, "__setattr__"))?
+        .call1((&cls, "__init__", cls.getattr(intern!(py, "__init__"))?))?;
+    m.add("DependenciesContainer", cls)
+}
+
+/// Register `Self` provider.
+///
+/// `Self` is a keyword in Rust, so the class is renamed once created.
+pub fn add_self_provider(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    let cls = py.get_type_bound::<SelfProvider>();
+    let type_setattr = py
+        .get_type_bound::<PyType>()
+        .getattr(intern!(py, "__setattr__"))?;
+    type_setattr.call1((&cls, "__name__", "Self"))?;
+    type_setattr.call1((&cls, "__qualname__", "Self"))?;
+    m.add("Self", cls)
+}
+
+/// List provider provides a list of values.
+///
+/// Provider elements are called on every call, call arguments are appended.
+#[pyclass(extends=Provider, module="inj", subclass)]
+#[derive(Default)]
+pub struct List {
+    injections: Injections,
+}
+
+#[pymethods]
+impl List {
+    #[new]
+    #[pyo3(signature = (*args))]
+    fn new(args: Vec<PyObject>) -> (Self, Provider) {
+        let this = Self {
+            injections: Injections::new(args, None),
+        };
+        (this, Provider::new())
+    }
+
+    fn _provide<'py>(
+        &self,
+        py: Python<'py>,
+        args: &Bound<'py, PyTuple>,
+        _kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyList>> {
+        let (args, _) = self.injections.call_args(py, args, None)?;
+        Ok(PyList::new_bound(py, args))
+    }
+
+    /// Return positional argument injections.
+    #[getter]
+    fn args(&self) -> Vec<PyObject> {
+        self.injections.args.clone()
+    }
+
+    /// Add positional argument injections.
+    #[pyo3(signature = (*args))]
+    fn add_args<'py>(slf: &Bound<'py, Self>, args: Vec<PyObject>) -> Bound<'py, Self> {
+        slf.borrow_mut().injections.add_args(args);
+        slf.clone()
+    }
+
+    /// Set positional argument injections, dropping previous ones.
+    #[pyo3(signature = (*args))]
+    fn set_args<'py>(slf: &Bound<'py, Self>, args: Vec<PyObject>) -> Bound<'py, Self> {
+        slf.borrow_mut().injections.set_args(args);
+        slf.clone()
+    }
+
+    /// Drop positional argument injections.
+    fn clear_args<'py>(slf: &Bound<'py, Self>) -> Bound<'py, Self> {
+        slf.borrow_mut().injections.clear_args();
+        slf.clone()
+    }
+
+    #[getter]
+    fn related(slf: &Bound<'_, Self>) -> Vec<PyObject> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let base: &Provider = this.as_ref();
+        providers_only(py, this.injections.values().chain(base.overridden.iter()))
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let injections = slf.borrow().injections.deepcopy(memo)?;
+            copied.borrow_mut().injections = injections;
+            Ok(())
+        })
+    }
+}
+
+/// Dict provider provides a dictionary of values.
+///
+/// Provider values are called on every call, call keyword arguments are
+/// merged in. Keys other than strings can be passed with ``dict_``.
+#[pyclass(extends=Provider, module="inj", subclass)]
+#[derive(Default)]
+pub struct Dict {
+    kwargs: Vec<(PyObject, PyObject)>,
 }
 
 #[pymethods]
-impl Provider {
+impl Dict {
     #[new]
-    fn new() -> Self {
-        Self::default()
+    #[pyo3(signature = (dict_=None, **kwargs))]
+    fn new(
+        dict_: Option<&Bound<'_, PyDict>>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<(Self, Provider)> {
+        let mut this = Self::default();
+        this.extend(dict_, kwargs)?;
+        Ok((this, Provider::new()))
+    }
+
+    fn _provide<'py>(
+        &self,
+        py: Python<'py>,
+        _args: &Bound<'py, PyTuple>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let provided = PyDict::new_bound(py);
+        for (key, value) in &self.kwargs {
+            provided.set_item(key, provide_injection(value.bind(py))?)?;
+        }
+        if let Some(kwargs) = kwargs {
+            provided.update(kwargs.as_mapping())?;
+        }
+        Ok(provided)
+    }
+
+    /// Return keyword argument injections.
+    #[getter]
+    fn kwargs<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let kwargs = PyDict::new_bound(py);
+        for (key, value) in &self.kwargs {
+            kwargs.set_item(key, value)?;
+        }
+        Ok(kwargs)
+    }
+
+    /// Add keyword argument injections.
+    #[pyo3(signature = (dict_=None, **kwargs))]
+    fn add_kwargs<'py>(
+        slf: &Bound<'py, Self>,
+        dict_: Option<&Bound<'py, PyDict>>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, Self>> {
+        slf.borrow_mut().extend(dict_, kwargs)?;
+        Ok(slf.clone())
+    }
+
+    /// Set keyword argument injections, dropping previous ones.
+    #[pyo3(signature = (dict_=None, **kwargs))]
+    fn set_kwargs<'py>(
+        slf: &Bound<'py, Self>,
+        dict_: Option<&Bound<'py, PyDict>>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, Self>> {
+        let mut this = slf.borrow_mut();
+        this.kwargs.clear();
+        this.extend(dict_, kwargs)?;
+        Ok(slf.clone())
+    }
+
+    /// Drop keyword argument injections.
+    fn clear_kwargs<'py>(slf: &Bound<'py, Self>) -> Bound<'py, Self> {
+        slf.borrow_mut().kwargs.clear();
+        slf.clone()
+    }
+
+    #[getter]
+    fn related(slf: &Bound<'_, Self>) -> Vec<PyObject> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let base: &Provider = this.as_ref();
+        providers_only(
+            py,
+            this.kwargs
+                .iter()
+                .map(|(_, value)| value)
+                .chain(base.overridden.iter()),
+        )
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let py = slf.py();
+            let values: Vec<PyObject> = slf
+                .borrow()
+                .kwargs
+                .iter()
+                .map(|(_, value)| value.clone_ref(py))
+                .collect();
+            let values: Vec<PyObject> = deepcopy(values.into_py(py).bind(py), memo)?.extract()?;
+            let kwargs = slf
+                .borrow()
+                .kwargs
+                .iter()
+                .map(|(key, _)| key.clone_ref(py))
+                .zip(values)
+                .collect();
+            copied.borrow_mut().kwargs = kwargs;
+            Ok(())
+        })
     }
+}
 
-    // /// Helper function for creating its delegates.
-    // ///
-    // /// ```
-    // /// let provider = Factory::new::<object>();
-    // /// let delegate = provider.delegate();
-    // ///
-    // /// let delegated = delegate();
-    // ///
-    // /// assert!(provider.is(delegated));
-    // /// ```
-    // #[pyo3(name = "delegate")]
-    // fn delegate<'p>(&self, py: Python<'p>) -> PyResult<&PyAny> {
-    //     let delegate = PyDelegate::new(py, self)?;
-    //
-    //     Ok(delegate)
-    // }
-    // /// Function for overriding providers.
-    // ///
-    // /// ```
-    // /// let provider1 = Factory::new::<SomeClass>();
-    // /// let provider2 = Factory::new::<ChildSomeClass>();
-    // ///
-    // /// provider1.override(provider2);
-    // ///
-    // /// let some_instance = provider1.call();
-    // /// assert!(some_instance.is::<ChildSomeClass>());
-    // /// ```
-    // #[pyo3(name = "override")]
-    // fn override<'p>(&mut self, py: Python<'p>, provider: &PyAny) -> PyResult<()> {
-    //     // Keep track of last overriding provider
-    //     self.__last_overriding = Some(provider.clone_ref(py));
-    //
-    //     // Add provider to overriding providers list
-    //     self.overridden.push(provider.clone_ref(py));
-    //
-    //     Ok(())
-    // }
+impl Dict {
+    /// Add injections, replacing ones with the same key
+    fn extend(
+        &mut self,
+        dict_: Option<&Bound<'_, PyDict>>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<()> {
+        for items in dict_.into_iter().chain(kwargs) {
+            for (key, value) in items.iter() {
+                let existing = self
+                    .kwargs
+                    .iter()
+                    .position(|(existing, _)| key.eq(existing).unwrap_or(false));
+                match existing {
+                    Some(position) => self.kwargs[position].1 = value.unbind(),
+                    None => self.kwargs.push((key.unbind(), value.unbind())),
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
+/// Selector provider selects provider based on the selector value.
+///
+/// ```python
+/// storage = Selector(
+///     config.storage,
+///     local=Callable(LocalStorage),
+///     s3=Callable(S3Storage),
+/// )
+/// ```
 #[pyclass(extends=Provider, module="inj", subclass)]
 #[derive(Default)]
-pub struct Dependency {
-    instance_of: Option<Py<PyType>>,
-    default: Option<Py<PyAny>>,
-    parent: Option<Py<PyObject>>,
+pub struct Selector {
+    selector: Option<PyObject>,
+    providers: HashMap<String, PyObject>,
 }
 
 #[pymethods]
-impl Dependency {
+impl Selector {
     #[new]
-    #[pyo3(signature = (instance_of=None, default=None, **kwargs))]
+    #[pyo3(signature = (selector=None, **providers))]
     fn new(
-        // py: Python,
-        instance_of: Option<Py<PyType>>,
-        default: Option<Py<PyAny>>,
-        kwargs: Option<Py<PyDict>>,
+        selector: Option<PyObject>,
+        providers: Option<HashMap<String, PyObject>>,
     ) -> (Self, Provider) {
         let this = Self {
-            instance_of: instance_of,
-            default: default,
-            parent: None,
-            // parent: kwargs.map(|kw| {
-            //     let kw_ref = kw.bind(py);
-            //     kw_ref
-            //         .clone()
-            //         .get("__parent__")
-            //         .unwrap_or(None)
-            //         .to_object()
-            //         .ok()
-            // }),
+            selector,
+            providers: providers.unwrap_or_default(),
         };
-        let base = Provider::new();
-        (this, base)
+        (this, Provider::new())
+    }
+
+    /// Return branch provider.
+    fn __getattr__(&self, py: Python, name: &str) -> PyResult<PyObject> {
+        match self.providers.get(name) {
+            Some(provider) if !name.starts_with("__") => Ok(provider.clone_ref(py)),
+            _ => Err(PyAttributeError::new_err(name.to_owned())),
+        }
+    }
+
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let Some(selector) = slf.borrow().selector.clone() else {
+            return Err(errors::Error::new_err(format!(
+                "Selector \"{}\" has no selector",
+                provider_label(slf.as_any())?
+            )));
+        };
+        let key = selector.call0(py)?.into_bound(py);
+        if key.is_none() {
+            return Err(errors::Error::new_err(format!(
+                "Selector \"{}\" value is undefined, valid keys: {}",
+                provider_label(slf.as_any())?,
+                slf.borrow().valid_keys()
+            )));
+        }
+        let provider = key
+            .extract::<String>()
+            .ok()
+            .and_then(|key| slf.borrow().providers.get(&key).cloned());
+        match provider {
+            Some(provider) => Ok(provider.bind(py).call(args, kwargs)?.unbind()),
+            None => Err(errors::Error::new_err(format!(
+                "Selector \"{}\" has no \"{}\" provider, valid keys: {}",
+                provider_label(slf.as_any())?,
+                key.str()?,
+                slf.borrow().valid_keys()
+            ))),
+        }
+    }
+
+    /// Return selector.
+    #[getter]
+    fn selector(&self) -> Option<PyObject> {
+        self.selector.clone()
+    }
+
+    /// Set selector.
+    fn set_selector<'py>(slf: &Bound<'py, Self>, selector: PyObject) -> Bound<'py, Self> {
+        slf.borrow_mut().selector = Some(selector);
+        slf.clone()
+    }
+
+    /// Return branch providers.
+    #[getter]
+    fn providers(&self) -> HashMap<String, PyObject> {
+        self.providers.clone()
+    }
+
+    /// Set branch providers, dropping previous ones.
+    #[pyo3(signature = (**providers))]
+    fn set_providers<'py>(
+        slf: &Bound<'py, Self>,
+        providers: Option<HashMap<String, PyObject>>,
+    ) -> Bound<'py, Self> {
+        slf.borrow_mut().providers = providers.unwrap_or_default();
+        slf.clone()
+    }
+
+    #[getter]
+    fn related(slf: &Bound<'_, Self>) -> Vec<PyObject> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let base: &Provider = this.as_ref();
+        providers_only(
+            py,
+            this.selector
+                .iter()
+                .chain(this.providers.values())
+                .chain(base.overridden.iter()),
+        )
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let py = slf.py();
+            let (selector, providers) = {
+                let this = slf.borrow();
+                (this.selector.clone(), this.providers.clone())
+            };
+            let selector = deepcopy_option(selector, memo)?;
+            let providers = deepcopy(providers.into_py(py).bind(py), memo)?.extract()?;
+            let mut copied = copied.borrow_mut();
+            copied.selector = selector;
+            copied.providers = providers;
+            Ok(())
+        })
+    }
+}
+
+impl Selector {
+    fn valid_keys(&self) -> String {
+        let mut keys: Vec<&String> = self.providers.keys().collect();
+        keys.sort();
+        keys.iter()
+            .map(|key| format!("\"{key}\""))
+            .collect::<Vec<_>>()
+            .join(", ")
     }
 }
 
+/// Aggregate provider groups providers by key.
+///
+/// First call argument selects the provider, the rest of arguments are
+/// passed to it:
+///
+/// ```python
+/// handlers = Aggregate(csv=Callable(CsvHandler), json=Callable(JsonHandler))
+/// handler = handlers("json", path)
+/// ```
 #[pyclass(extends=Provider, module="inj", subclass)]
-pub struct DependenciesContainer {
-    providers: HashMap<String, Py<PyObject>>,
-    parent: Option<Py<PyObject>>,
+pub struct Aggregate {
+    providers: Py<PyDict>,
 }
 
 #[pymethods]
-impl DependenciesContainer {
+impl Aggregate {
     #[new]
-    #[pyo3(signature = (**dependencies))]
-    fn new(dependencies: Option<HashMap<String, Py<PyAny>>>) -> (Self, Provider) {
-        let this = DependenciesContainer {
-            providers: HashMap::new(),
-            parent: None,
+    #[pyo3(signature = (provider_dict=None, **provider_kwargs))]
+    fn new(
+        py: Python,
+        provider_dict: Option<&Bound<'_, PyDict>>,
+        provider_kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<(Self, Provider)> {
+        let providers = PyDict::new_bound(py);
+        Self::update_providers(&providers, provider_dict, provider_kwargs)?;
+        let this = Self {
+            providers: providers.unbind(),
         };
-        let base = Provider::new();
-        (this, base)
+        Ok((this, Provider::new()))
+    }
+
+    /// Return provider by its key.
+    fn __getattr__(&self, py: Python, name: &str) -> PyResult<PyObject> {
+        match self.providers.bind(py).get_item(name)? {
+            Some(provider) if !name.starts_with("__") => Ok(provider.unbind()),
+            _ => Err(PyAttributeError::new_err(name.to_owned())),
+        }
+    }
+
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let Ok(key) = args.get_item(0) else {
+            return Err(errors::Error::new_err(format!(
+                "Aggregate \"{}\" missing first argument with the provider key",
+                provider_label(slf.as_any())?
+            )));
+        };
+        let providers = slf.borrow().providers.clone_ref(py);
+        let providers = providers.bind(py);
+        match providers.get_item(&key)? {
+            Some(provider) => Ok(provider
+                .call(args.get_slice(1, args.len()), kwargs)?
+                .unbind()),
+            None => {
+                let mut keys = providers
+                    .keys()
+                    .iter()
+                    .map(|key| Ok(format!("\"{}\"", key.str()?)))
+                    .collect::<PyResult<Vec<_>>>()?;
+                keys.sort();
+                Err(errors::Error::new_err(format!(
+                    "Aggregate \"{}\" has no \"{}\" provider, valid keys: {}",
+                    provider_label(slf.as_any())?,
+                    key.str()?,
+                    keys.join(", ")
+                )))
+            }
+        }
+    }
+
+    /// Return providers dictionary.
+    #[getter]
+    fn providers<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        self.providers.bind(py).copy()
+    }
+
+    /// Set providers, dropping previous ones.
+    #[pyo3(signature = (provider_dict=None, **provider_kwargs))]
+    fn set_providers<'py>(
+        slf: &Bound<'py, Self>,
+        provider_dict: Option<&Bound<'py, PyDict>>,
+        provider_kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, Self>> {
+        let py = slf.py();
+        let providers = PyDict::new_bound(py);
+        Self::update_providers(&providers, provider_dict, provider_kwargs)?;
+        slf.borrow_mut().providers = providers.unbind();
+        Ok(slf.clone())
+    }
+
+    #[getter]
+    fn related(slf: &Bound<'_, Self>) -> Vec<PyObject> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let base: &Provider = this.as_ref();
+        let providers: Vec<PyObject> = this
+            .providers
+            .bind(py)
+            .values()
+            .iter()
+            .map(Bound::unbind)
+            .collect();
+        providers_only(py, providers.iter().chain(base.overridden.iter()))
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let providers = slf.borrow().providers.clone_ref(slf.py());
+            let providers = deepcopy(providers.bind(slf.py()), memo)?.downcast_into::<PyDict>()?;
+            copied.borrow_mut().providers = providers.unbind();
+            Ok(())
+        })
     }
 }
 
-// Container provider provides an instance of declarative container.
+impl Aggregate {
+    fn update_providers(
+        providers: &Bound<'_, PyDict>,
+        provider_dict: Option<&Bound<'_, PyDict>>,
+        provider_kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<()> {
+        for items in provider_dict.into_iter().chain(provider_kwargs) {
+            for (key, provider) in items.iter() {
+                if !provider.is_instance_of::<Provider>() {
+                    return Err(errors::Error::new_err(format!(
+                        "Aggregate can contain only providers, got {} for \"{}\"",
+                        provider.repr()?,
+                        key.str()?
+                    )));
+                }
+                providers.set_item(key, provider)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Aggregate of factories.
+///
+/// Kept for compatibility, behaves exactly as `Aggregate`.
+#[pyclass(extends=Aggregate, module="inj", subclass)]
+pub struct FactoryAggregate;
+
+#[pymethods]
+impl FactoryAggregate {
+    #[new]
+    #[pyo3(signature = (provider_dict=None, **provider_kwargs))]
+    fn new(
+        py: Python,
+        provider_dict: Option<&Bound<'_, PyDict>>,
+        provider_kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyClassInitializer<Self>> {
+        let (aggregate, base) = Aggregate::new(py, provider_dict, provider_kwargs)?;
+        Ok(PyClassInitializer::from(base)
+            .add_subclass(aggregate)
+            .add_subclass(Self))
+    }
+}
+
+/// Delegate provider returns the delegated provider "as is".
+///
+/// ```python
+/// pool = Callable(WorkerPool, worker_factory=Delegate(worker))
+/// ```
 #[pyclass(extends=Provider, module="inj", subclass)]
-pub struct Container {
-    container_cls: Option<Py<PyType>>,
+#[derive(Default)]
+pub struct Delegate {
+    provides: Option<PyObject>,
 }
 
 #[pymethods]
-impl Container {
+impl Delegate {
     #[new]
-    fn new() -> (Self, Provider) {
-        // def __init__(self, container_cls=None, container=None, **overriding_providers):
-        //     """Initialize provider."""
-        //     self.__container_cls = container_cls
-        //     self.__overriding_providers = overriding_providers
-        //
-        //     if container is None and container_cls:
-        //         container = container_cls()
-        //         container.assign_parent(self)
-        //     self.__container = container
-        //
-        //     if self.__container and self.__overriding_providers:
-        //         self.apply_overridings()
-        //
-        //     self.__parent = None
-        //
-        //     super(Container, self).__init__()
+    #[pyo3(signature = (provides=None))]
+    fn new(provides: Option<&Bound<'_, PyAny>>) -> PyResult<(Self, Provider)> {
+        if let Some(provides) = provides {
+            Self::check_provides(provides)?;
+        }
+        let this = Self {
+            provides: provides.map(|provides| provides.clone().unbind()),
+        };
+        Ok((this, Provider::new()))
+    }
 
-        let this = Container {
-            container_cls: None,
+    fn _provide(
+        &self,
+        py: Python,
+        _args: &Bound<'_, PyTuple>,
+        _kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyObject {
+        match &self.provides {
+            Some(provides) => provides.clone_ref(py),
+            None => py.None(),
+        }
+    }
+
+    /// Return delegated provider.
+    #[getter]
+    fn provides(&self) -> Option<PyObject> {
+        self.provides.clone()
+    }
+
+    /// Set delegated provider.
+    fn set_provides<'py>(
+        slf: &Bound<'py, Self>,
+        provides: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, Self>> {
+        Self::check_provides(provides)?;
+        slf.borrow_mut().provides = Some(provides.clone().unbind());
+        Ok(slf.clone())
+    }
+
+    #[getter]
+    fn related(slf: &Bound<'_, Self>) -> Vec<PyObject> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let base: &Provider = this.as_ref();
+        providers_only(py, this.provides.iter().chain(base.overridden.iter()))
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let provides = slf.borrow().provides.clone();
+            let provides = deepcopy_option(provides, memo)?;
+            copied.borrow_mut().provides = provides;
+            Ok(())
+        })
+    }
+}
+
+impl Delegate {
+    /// Create delegate of the provider
+    pub(crate) fn of<'py>(provider: &Bound<'py, PyAny>) -> PyResult<Bound<'py, Self>> {
+        let delegate = Self {
+            provides: Some(provider.clone().unbind()),
         };
-        let base = Provider::new();
-        (this, base)
+        Bound::new(
+            provider.py(),
+            PyClassInitializer::from(Provider::new()).add_subclass(delegate),
+        )
+    }
+
+    fn check_provides(provides: &Bound<'_, PyAny>) -> PyResult<()> {
+        if !provides.is_instance_of::<Provider>() {
+            return Err(errors::Error::new_err(format!(
+                "Delegate expected to get a provider, got {}",
+                provides.repr()?
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Provided instance provider builds lazy chains over the provided object.
+///
+/// ```python
+/// session = client.provided.sessions["default"].call(timeout=10)
+/// ```
+///
+/// Every step of the chain is a provider evaluated at injection time.
+#[pyclass(extends=Provider, module="inj", subclass)]
+#[derive(Default)]
+pub struct ProvidedInstance {
+    provides: Option<PyObject>,
+}
+
+#[pymethods]
+impl ProvidedInstance {
+    #[new]
+    #[pyo3(signature = (provides=None))]
+    fn new(provides: Option<PyObject>) -> (Self, Provider) {
+        (Self { provides }, Provider::new())
+    }
+
+    /// Return attribute getter of the provided object.
+    fn __getattr__<'py>(slf: &Bound<'py, Self>, name: &str) -> PyResult<Bound<'py, PyAny>> {
+        if name.starts_with("__") {
+            return Err(PyAttributeError::new_err(name.to_owned()));
+        }
+        let getter = AttributeGetter {
+            name: Some(name.to_owned()),
+        };
+        Ok(Self::chain(slf.as_any(), getter)?.into_any())
+    }
+
+    /// Return item getter of the provided object.
+    fn __getitem__<'py>(
+        slf: &Bound<'py, Self>,
+        name: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, ItemGetter>> {
+        let getter = ItemGetter {
+            name: Some(name.clone().unbind()),
+        };
+        Self::chain(slf.as_any(), getter)
+    }
+
+    /// Return method caller of the provided object.
+    ///
+    /// Provider arguments are injected the same way as in `Callable`.
+    #[pyo3(signature = (*args, **kwargs))]
+    fn call<'py>(
+        slf: &Bound<'py, Self>,
+        args: Vec<PyObject>,
+        kwargs: Option<IndexMap<String, PyObject>>,
+    ) -> PyResult<Bound<'py, MethodCaller>> {
+        let caller = MethodCaller {
+            injections: Injections::new(args, kwargs),
+        };
+        Self::chain(slf.as_any(), caller)
+    }
+
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        Ok(Self::provide_instance(slf, args, kwargs)?.unbind())
+    }
+
+    /// Return provider of the instance.
+    #[getter]
+    fn provides(&self) -> Option<PyObject> {
+        self.provides.clone()
+    }
+
+    #[getter]
+    fn related(slf: &Bound<'_, Self>) -> Vec<PyObject> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let base: &Provider = this.as_ref();
+        providers_only(py, this.provides.iter().chain(base.overridden.iter()))
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| Self::copy_provides(slf, copied, memo))
+    }
+}
+
+impl ProvidedInstance {
+    /// Create next step of the chain over the provider
+    fn chain<'py, T>(provides: &Bound<'py, PyAny>, step: T) -> PyResult<Bound<'py, T>>
+    where
+        T: pyo3::PyClass<BaseType = Self>,
+    {
+        let base = Self {
+            provides: Some(provides.clone().unbind()),
+        };
+        Bound::new(
+            provides.py(),
+            PyClassInitializer::from((base, Provider::new())).add_subclass(step),
+        )
+    }
+
+    /// Provide the instance the chain step is applied to
+    fn provide_instance<'py>(
+        slf: &Bound<'py, Self>,
+        args: &Bound<'py, PyTuple>,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let Some(provides) = slf.borrow().provides.clone() else {
+            return Err(errors::Error::new_err(format!(
+                "Provider \"{}\" has no provides",
+                provider_label(slf.as_any())?
+            )));
+        };
+        provides.into_bound(slf.py()).call(args, kwargs)
+    }
+
+    /// Copy provided instance, used by chain steps on deep copy
+    fn copy_provides(
+        slf: &Bound<'_, Self>,
+        copied: &Bound<'_, Self>,
+        memo: &Bound<'_, PyDict>,
+    ) -> PyResult<()> {
+        let provides = slf.borrow().provides.clone();
+        copied.borrow_mut().provides = deepcopy_option(provides, memo)?;
+        Ok(())
     }
 }
 
+/// Apply chain step to the instance, awaiting it first if needed.
+fn apply_provided<F>(py: Python, instance: Bound<'_, PyAny>, step: F) -> PyResult<PyObject>
+where
+    F: FnOnce(Python<'_>, &Bound<'_, PyAny>) -> PyResult<PyObject> + Send + 'static,
+{
+    if is_awaitable(&instance) {
+        let awaitable = Awaitable::map(instance.unbind(), move |py, instance| {
+            step(py, instance?.bind(py))
+        });
+        return Ok(Bound::new(py, awaitable)?.into_any().unbind());
+    }
+    step(py, &instance)
+}
+
+/// Attribute getter provider returns attribute of the provided object.
+#[pyclass(extends=ProvidedInstance, module="inj", subclass)]
+#[derive(Default)]
+pub struct AttributeGetter {
+    name: Option<String>,
+}
+
+#[pymethods]
+impl AttributeGetter {
+    #[new]
+    #[pyo3(signature = (provides=None, name=None))]
+    fn new(provides: Option<PyObject>, name: Option<String>) -> PyClassInitializer<Self> {
+        PyClassInitializer::from(ProvidedInstance::new(provides)).add_subclass(Self { name })
+    }
+
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let Some(name) = slf.borrow().name.clone() else {
+            return Err(errors::Error::new_err(format!(
+                "Attribute getter \"{}\" has no name",
+                provider_label(slf.as_any())?
+            )));
+        };
+        let instance = ProvidedInstance::provide_instance(slf.downcast()?, args, kwargs)?;
+        apply_provided(py, instance, move |_, instance| {
+            Ok(instance.getattr(name.as_str())?.unbind())
+        })
+    }
+
+    /// Return attribute name.
+    #[getter]
+    fn name(&self) -> Option<String> {
+        self.name.clone()
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            copied.borrow_mut().name = slf.borrow().name.clone();
+            ProvidedInstance::copy_provides(slf.downcast()?, copied.downcast()?, memo)
+        })
+    }
+}
+
+/// Item getter provider returns item of the provided object.
+#[pyclass(extends=ProvidedInstance, module="inj", subclass)]
+#[derive(Default)]
+pub struct ItemGetter {
+    name: Option<PyObject>,
+}
+
+#[pymethods]
+impl ItemGetter {
+    #[new]
+    #[pyo3(signature = (provides=None, name=None))]
+    fn new(provides: Option<PyObject>, name: Option<PyObject>) -> PyClassInitializer<Self> {
+        PyClassInitializer::from(ProvidedInstance::new(provides)).add_subclass(Self { name })
+    }
+
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let Some(name) = slf.borrow().name.clone() else {
+            return Err(errors::Error::new_err(format!(
+                "Item getter \"{}\" has no name",
+                provider_label(slf.as_any())?
+            )));
+        };
+        let instance = ProvidedInstance::provide_instance(slf.downcast()?, args, kwargs)?;
+        apply_provided(py, instance, move |py, instance| {
+            Ok(instance.get_item(name.bind(py))?.unbind())
+        })
+    }
+
+    /// Return item name.
+    #[getter]
+    fn name(&self) -> Option<PyObject> {
+        self.name.clone()
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let name = slf.borrow().name.clone();
+            copied.borrow_mut().name = deepcopy_option(name, memo)?;
+            ProvidedInstance::copy_provides(slf.downcast()?, copied.downcast()?, memo)
+        })
+    }
+}
+
+/// Method caller provider calls the provided object with injections.
+///
+/// It is usually created with `.call()` on an attribute getter, so the
+/// provided object is a bound method.
+#[pyclass(extends=ProvidedInstance, module="inj", subclass)]
+#[derive(Default)]
+pub struct MethodCaller {
+    injections: Injections,
+}
+
+#[pymethods]
+impl MethodCaller {
+    #[new]
+    #[pyo3(signature = (provides=None, *args, **kwargs))]
+    fn new(
+        provides: Option<PyObject>,
+        args: Vec<PyObject>,
+        kwargs: Option<IndexMap<String, PyObject>>,
+    ) -> PyClassInitializer<Self> {
+        let this = Self {
+            injections: Injections::new(args, kwargs),
+        };
+        PyClassInitializer::from(ProvidedInstance::new(provides)).add_subclass(this)
+    }
+
+    fn _provide(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        let empty = PyTuple::empty_bound(py);
+        let method = ProvidedInstance::provide_instance(slf.downcast()?, &empty, None)?;
+        let injections = slf.borrow().injections.clone();
+        let (args, kwargs) = injections.call_args(py, args, kwargs)?;
+        let awaits = Provider::awaits_injections(slf.as_any());
+        let (args, kwargs) = (args.unbind(), kwargs.unbind());
+        apply_provided(py, method, move |py, method| {
+            let (args, kwargs) = (args.bind(py), kwargs.bind(py));
+            if awaits {
+                AsyncCall::new(method.clone().unbind(), args, kwargs, false).call(py)
+            } else {
+                Ok(method.call(args, Some(kwargs))?.unbind())
+            }
+        })
+    }
+
+    /// Return positional argument injections.
+    #[getter]
+    fn args(&self) -> Vec<PyObject> {
+        self.injections.args.clone()
+    }
+
+    /// Return keyword argument injections.
+    #[getter]
+    fn kwargs(&self) -> IndexMap<String, PyObject> {
+        self.injections.kwargs.clone()
+    }
+
+    #[getter]
+    fn related(slf: &Bound<'_, Self>) -> Vec<PyObject> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let instance = slf.borrow().into_super();
+        let base: &Provider = instance.as_ref();
+        providers_only(
+            py,
+            instance
+                .provides
+                .iter()
+                .chain(this.injections.values())
+                .chain(base.overridden.iter()),
+        )
+    }
+
+    fn __deepcopy__<'py>(
+        slf: &Bound<'py, Self>,
+        memo: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        deepcopy_provider(slf, memo, |copied| {
+            let injections = slf.borrow().injections.deepcopy(memo)?;
+            copied.borrow_mut().injections = injections;
+            ProvidedInstance::copy_provides(slf.downcast()?, copied.downcast()?, memo)
+        })
+    }
+}
+
+fn provide_injection<'py>(injection: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+    if injection.is_instance_of::<Provider>() {
+        injection.call0()
+    } else {
+        Ok(injection.clone())
+    }
+}
+
+/// Check if provider needs a parent back-reference when added to a container.
+pub(crate) fn is_child_provider(provider: &Bound<'_, PyAny>) -> PyResult<bool> {
+    Ok(provider.is_instance_of::<Dependency>()
+        || provider.is_instance_of::<DependenciesContainer>()
+        || provider.is_instance_of::<Container>())
+}
+
 #[pyfunction]
-pub fn traverse(
-    py: Python,
+pub fn traverse<'py>(
+    py: Python<'py>,
     providers: Vec<Py<Provider>>,
     types: Option<Vec<Py<PyType>>>,
-) -> PyResult<Bound<'_, PyIterator>> {
+) -> PyResult<Bound<'py, PyIterator>> {
     let traverse = Traverse::new(py, providers, types);
     PyIterator::from_bound_object(traverse.into_py(py).bind(py))
 }
 
-// #[pyclass]
-// struct TraversePyIter {
-//     inner: IntoIterator<Item = Py<Provider>>,
-// }
-//
-// #[pymethods]
-// impl TraversePyIter {
-//     fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
-//         slf
-//     }
-//
-//     fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<Py<Provider>> {
-//         slf.inner.next()
-//     }
-// }
-//
-
 #[pyclass]
 pub struct Traverse {
-    providers: Vec<Py<Provider>>,
     visited: HashSet<usize>,
     to_visit: VecDeque<Py<Provider>>,
     types: Option<Py<PyTuple>>,
@@ -200,9 +3648,8 @@ pub struct Traverse {
 impl Traverse {
     pub fn new(py: Python, providers: Vec<Py<Provider>>, types: Option<Vec<Py<PyType>>>) -> Self {
         Self {
-            providers: providers.clone(),
             visited: HashSet::new(),
-            to_visit: providers.clone().into(),
+            to_visit: providers.into(),
             types: types.map(|ty| PyTuple::new_bound(py, ty).unbind()),
         }
     }
@@ -210,12 +3657,12 @@ impl Traverse {
         self.visited.insert(visiting.as_ptr() as usize);
         visiting
             .bind(py)
-            .getattr("related")?
+            .getattr(intern!(py, "related"))?
             .extract::<Vec<Bound<'_, Provider>>>()?
             .iter()
-            .filter(|x| self.visited.contains(&(x.as_ptr() as usize)))
+            .filter(|x| !self.visited.contains(&(x.as_ptr() as usize)))
             .for_each(|x| self.to_visit.push_back(x.clone().unbind()));
-        return Ok(visiting);
+        Ok(visiting)
     }
 }
 
@@ -227,6 +3674,9 @@ impl Traverse {
 
     fn __next__(mut _self: PyRefMut<Self>, py: Python) -> Option<PyResult<Py<Provider>>> {
         while let Some(visiting) = _self.to_visit.pop_front() {
+            if _self.visited.contains(&(visiting.as_ptr() as usize)) {
+                continue;
+            }
             match _self.visit(py, visiting) {
                 Ok(provider) => {
                     if let Some(types) = &_self.types {
@@ -12,6 +12,7 @@ create_exception!(inj, SchemaError, PyException);
 pub struct SchemaProcessorV1 {
     schema: Py<PyDict>,
     container: Py<containers::Container>,
+    self_providers: Vec<String>,
 }
 
 impl SchemaProcessorV1 {
@@ -24,6 +25,7 @@ impl SchemaProcessorV1 {
                 .downcast::<containers::Container>()?
                 .clone()
                 .into(),
+            self_providers: Vec::new(),
         })
     }
 
@@ -43,7 +45,13 @@ impl SchemaProcessorV1 {
     }
 
     fn get_providers(&self, py: Python) -> PyResult<PyObject> {
-        Ok(self.container.bind(py).getattr("providers")?.into())
+        let container = self.container.bind(py);
+        let providers = container.getattr("providers")?.downcast_into::<PyDict>()?;
+        // `Self` providers are not container providers, but are part of schema
+        for name in &self.self_providers {
+            providers.set_item(name, container.getattr(name.as_str())?)?;
+        }
+        Ok(providers.into())
     }
 
     fn create_providers(
@@ -52,8 +60,9 @@ impl SchemaProcessorV1 {
         provider_schema: &Bound<'_, PyDict>,
         container: Option<Py<containers::Container>>,
     ) -> PyResult<()> {
-        let dynamic_container_type = py.get_type_bound::<containers::Container>();
+        let dynamic_container_type = py.get_type_bound::<containers::DynamicContainer>();
         let provider_container_type = py.get_type_bound::<providers::Container>();
+        let is_root = container.is_none();
         let container = container.unwrap_or(self.container.clone());
         let container = container.bind(py);
 
@@ -69,7 +78,10 @@ impl SchemaProcessorV1 {
                     .into()
             };
             let provider = provider.bind(py);
-            container.call_method1("set_provider", (provider_name, provider.clone()))?;
+            if is_root && provider.is_instance_of::<providers::SelfProvider>() {
+                self.self_providers.push(provider_name.extract()?);
+            }
+            container.call_method1("set_provider", (&provider_name, provider.clone()))?;
 
             if providers::Container::is_type_of_bound(provider) {
                 self.create_providers(
@@ -162,11 +174,11 @@ impl SchemaProcessorV1 {
 
     fn _resolve_injection(&self, py: Python, arg: &Bound<'_, PyAny>) -> PyResult<PyObject> {
         Ok(match arg {
-            _ if _is_str_starts_with_container(&arg)? => {
+            _ if _is_str_starts_with_container(arg)? => {
                 let arg: &str = arg.extract()?;
                 self._resolve_provider(py, &arg[10..])?
             }
-            _ if PyDict::is_type_of_bound(&arg) => {
+            _ if PyDict::is_type_of_bound(arg) => {
                 let mut provider_args = Vec::<PyObject>::new();
                 let provider_type = _get_provider_cls(arg.get_item("provider")?.extract()?)?;
                 if let Ok(ref provides) = arg.get_item("provides") {
@@ -188,25 +200,152 @@ impl SchemaProcessorV1 {
         })
     }
 
+    /// Resolve provider path relative to the container.
+    ///
+    /// Path segments are provider attributes, items and method calls, e.g.
+    /// ``client.provided.sessions["default"].get(container.config)``. Items
+    /// and calls build provided instance chains of the provider.
     fn _resolve_provider(&self, py: Python, name: &str) -> PyResult<PyObject> {
-        let segments: Vec<&str> = name.split(".").collect();
-        let mut provider = self.container.getattr(py, *segments.get(0).unwrap())?;
+        let segments = _parse_provider_path(name)?;
+        let mut provider = self.container.clone_ref(py).into_any().into_bound(py);
+
+        for segment in segments {
+            provider = match segment {
+                PathSegment::Attribute(attribute) => provider.getattr(attribute.as_str())?,
+                PathSegment::Item(key) => provider.get_item(_literal(py, &key, true)?)?,
+                PathSegment::Call(arguments) => {
+                    let kwargs = PyDict::new_bound(py);
+                    let mut positional = Vec::new();
+                    for argument in _split_top_level(&arguments, ',') {
+                        let argument = argument.trim();
+                        if argument.is_empty() {
+                            continue;
+                        }
+                        match _split_keyword(argument) {
+                            Some((keyword, value)) => {
+                                kwargs.set_item(keyword, self._resolve_argument(py, value)?)?
+                            }
+                            None => positional.push(self._resolve_argument(py, argument)?),
+                        }
+                    }
+                    let args = PyTuple::new_bound(py, positional);
+                    provider.call_method("call", args, Some(&kwargs))?
+                }
+            };
+        }
+        Ok(provider.unbind())
+    }
 
-        for segment in &segments[1..] {
-            let (segment, have_parentheses) =
-                if let (Some(start), Some(end)) = (segment.find("("), segment.find(")")) {
-                    ((segment[start..end + 1]).to_string(), true)
-                } else {
-                    (segment.to_string(), false)
-                };
+    fn _resolve_argument(&self, py: Python, argument: &str) -> PyResult<PyObject> {
+        match argument.strip_prefix("container.") {
+            Some(name) => self._resolve_provider(py, name),
+            None => Ok(_literal(py, argument, false)?.unbind()),
+        }
+    }
+}
 
-            provider = provider.getattr(py, segment.as_str())?;
+/// Segment of a provider path
+enum PathSegment {
+    Attribute(String),
+    Item(String),
+    Call(String),
+}
 
-            if have_parentheses {
-                provider = provider.call0(py)?;
+fn _parse_provider_path(name: &str) -> PyResult<Vec<PathSegment>> {
+    let invalid = || SchemaError::new_err(format!("Invalid provider path \"{}\"", name));
+    let mut segments = Vec::new();
+    for part in _split_top_level(name, '.') {
+        let attribute_end = part.find(['[', '(']).unwrap_or(part.len());
+        let attribute = part[..attribute_end].trim();
+        if attribute.is_empty() {
+            return Err(invalid());
+        }
+        segments.push(PathSegment::Attribute(attribute.to_string()));
+
+        let mut rest = &part[attribute_end..];
+        while !rest.is_empty() {
+            let close = match rest.chars().next() {
+                Some('[') => ']',
+                Some('(') => ')',
+                _ => return Err(invalid()),
+            };
+            let end = _find_closing(rest, close).ok_or_else(invalid)?;
+            let inner = rest[1..end].to_string();
+            segments.push(match close {
+                ']' => PathSegment::Item(inner),
+                _ => PathSegment::Call(inner),
+            });
+            rest = &rest[end + 1..];
+        }
+    }
+    Ok(segments)
+}
+
+/// Split string by separator outside of quotes and brackets.
+fn _split_top_level(string: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut quote, mut start) = (0usize, None, 0);
+    for (index, char) in string.char_indices() {
+        match (quote, char) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(char),
+            (None, '[' | '(' | '{') => depth += 1,
+            (None, ']' | ')' | '}') => depth = depth.saturating_sub(1),
+            (None, c) if c == separator && depth == 0 => {
+                parts.push(&string[start..index]);
+                start = index + c.len_utf8();
             }
+            _ => {}
+        }
+    }
+    parts.push(&string[start..]);
+    parts
+}
+
+/// Find index of the bracket closing the one the string starts with.
+fn _find_closing(string: &str, close: char) -> Option<usize> {
+    let (mut depth, mut quote) = (0usize, None);
+    for (index, char) in string.char_indices() {
+        match (quote, char) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(char),
+            (None, '[' | '(' | '{') => depth += 1,
+            (None, c @ (']' | ')' | '}')) => {
+                depth -= 1;
+                if depth == 0 {
+                    return (c == close).then_some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split ``keyword=value`` call argument.
+fn _split_keyword(argument: &str) -> Option<(&str, &str)> {
+    let (keyword, value) = argument.split_once('=')?;
+    let keyword = keyword.trim();
+    let is_identifier = keyword.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && keyword.chars().next().is_some_and(|c| !c.is_ascii_digit());
+    (is_identifier && !value.starts_with('=')).then_some((keyword, value.trim()))
+}
+
+/// Evaluate Python literal, bare words are strings when allowed.
+fn _literal<'py>(py: Python<'py>, value: &str, bare: bool) -> PyResult<Bound<'py, PyAny>> {
+    let value = value.trim();
+    let ast = py.import_bound("ast")?;
+    match ast.call_method1("literal_eval", (value,)) {
+        Ok(literal) => Ok(literal),
+        Err(_) if bare => Ok(PyString::new_bound(py, value).into_any()),
+        Err(err) => {
+            let schema_err =
+                SchemaError::new_err(format!("Can not evaluate argument \"{}\"", value));
+            schema_err.set_cause(py, Some(err));
+            Err(schema_err)
         }
-        Ok(provider)
     }
 }
 
@@ -220,6 +359,20 @@ fn _get_provider_cls(provider_cls_name: &str) -> PyResult<Py<PyType>> {
 fn _fetch_provider_cls_from_std(provider_cls_name: &str) -> Option<Py<PyType>> {
     Python::with_gil(|py| match provider_cls_name {
         "Provider" => Some(providers::Provider::type_object_bound(py).into()),
+        "Dependency" => Some(providers::Dependency::type_object_bound(py).into()),
+        "Resource" => Some(providers::Resource::type_object_bound(py).into()),
+        "Callable" => Some(providers::Callable::type_object_bound(py).into()),
+        "Coroutine" => Some(providers::Coroutine::type_object_bound(py).into()),
+        "Factory" => Some(providers::Factory::type_object_bound(py).into()),
+        "Singleton" => Some(providers::Singleton::type_object_bound(py).into()),
+        "AbstractFactory" => Some(providers::AbstractFactory::type_object_bound(py).into()),
+        "AbstractSingleton" => Some(providers::AbstractSingleton::type_object_bound(py).into()),
+        "Scoped" => Some(providers::Scoped::type_object_bound(py).into()),
+        "Object" => Some(providers::Object::type_object_bound(py).into()),
+        "Self" => Some(providers::SelfProvider::type_object_bound(py).into()),
+        "List" => Some(providers::List::type_object_bound(py).into()),
+        "Dict" => Some(providers::Dict::type_object_bound(py).into()),
+        "Delegate" => Some(providers::Delegate::type_object_bound(py).into()),
         // TODO: add other classes
         _ => None,
     })
@@ -268,7 +421,7 @@ fn _import_string(string_name: &str) -> PyResult<PyObject> {
 }
 
 fn _is_str_starts_with_container(obj: &Bound<'_, PyAny>) -> PyResult<bool> {
-    Ok(PyString::is_type_of_bound(&obj)
+    Ok(PyString::is_type_of_bound(obj)
         && obj
             .call_method1("startswith", ("container.",))?
             .extract::<bool>()?)
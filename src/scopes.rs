@@ -0,0 +1,254 @@
+use crate::awaitables::{Awaitable, Step};
+use crate::errors;
+use pyo3::exceptions::{PyStopAsyncIteration, PyStopIteration};
+use pyo3::intern;
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+use pyo3::types::{PyDict, PyTuple};
+
+/// Context variable holding the innermost entered scope
+static CURRENT_SCOPE: GILOnceCell<PyObject> = GILOnceCell::new();
+
+fn current_scope_var(py: Python<'_>) -> PyResult<&Bound<'_, PyAny>> {
+    CURRENT_SCOPE
+        .get_or_try_init(py, || {
+            let kwargs = PyDict::new_bound(py);
+            kwargs.set_item("default", py.None())?;
+            Ok::<_, PyErr>(
+                py.import_bound(intern!(py, "contextvars"))?
+                    .getattr(intern!(py, "ContextVar"))?
+                    .call(("inj_scope",), Some(&kwargs))?
+                    .unbind(),
+            )
+        })
+        .map(|var| var.bind(py))
+}
+
+/// Named scope of `Scoped` providers instances.
+///
+/// ```python
+/// with container.scope("request") as scope:
+///     service = container.service()
+/// ```
+///
+/// Scope is bound to the current `contextvars` context, so asyncio tasks
+/// created inside it see it, while new threads start without one. `Scoped`
+/// providers keep their instance in the innermost entered scope of their
+/// name, so a nested scope of the same name gets its own instances.
+/// Generators provided in the scope are closed on exit in reverse order.
+#[pyclass(module = "inj")]
+pub struct Scope {
+    name: String,
+    container: Option<PyObject>,
+    parent: Option<Py<Scope>>,
+    instances: Py<PyDict>,
+    resources: Vec<PyObject>,
+    token: Option<PyObject>,
+}
+
+#[pymethods]
+impl Scope {
+    #[new]
+    #[pyo3(signature = (name, container=None))]
+    pub fn new(py: Python, name: String, container: Option<PyObject>) -> Self {
+        Self {
+            name,
+            container,
+            parent: None,
+            instances: PyDict::new_bound(py).unbind(),
+            resources: Vec::new(),
+            token: None,
+        }
+    }
+
+    /// Return scope name.
+    #[getter]
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Return container the scope is created by.
+    #[getter]
+    fn container(&self) -> Option<PyObject> {
+        self.container.clone()
+    }
+
+    /// Return enclosing scope.
+    #[getter]
+    fn parent(&self) -> Option<Py<Scope>> {
+        self.parent.clone()
+    }
+
+    /// Check if scope is entered.
+    #[getter]
+    fn active(&self) -> bool {
+        self.token.is_some()
+    }
+
+    fn __enter__<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, Self>> {
+        let py = slf.py();
+        if slf.borrow().token.is_some() {
+            return Err(errors::Error::new_err(format!(
+                "Scope \"{}\" is already entered",
+                slf.borrow().name
+            )));
+        }
+        let var = current_scope_var(py)?;
+        let parent = var.call_method0(intern!(py, "get"))?;
+        let token = var.call_method1(intern!(py, "set"), (slf,))?;
+        let mut this = slf.borrow_mut();
+        this.parent = parent.downcast_into::<Scope>().ok().map(Bound::unbind);
+        this.token = Some(token.unbind());
+        Ok(slf.clone())
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(slf: &Bound<'_, Self>, _args: &Bound<'_, PyTuple>) -> PyResult<bool> {
+        let py = slf.py();
+        let resources = Self::exit(slf)?;
+        let mut error = None;
+        for resource in resources.into_iter().rev() {
+            let resource = resource.bind(py);
+            let result = if is_async_generator(resource)? {
+                Err(errors::Error::new_err(format!(
+                    "Scope \"{}\" has async resources, use \"async with\"",
+                    slf.borrow().name
+                )))
+            } else {
+                close_generator(resource)
+            };
+            if let Err(err) = result {
+                error.get_or_insert(err);
+            }
+        }
+        match error {
+            Some(err) => Err(err),
+            None => Ok(false),
+        }
+    }
+
+    fn __aenter__(slf: &Bound<'_, Self>) -> PyResult<Awaitable> {
+        let scope = Self::__enter__(slf)?.into_any().unbind();
+        Ok(Awaitable::ready(scope))
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __aexit__(slf: &Bound<'_, Self>, _args: &Bound<'_, PyTuple>) -> PyResult<Awaitable> {
+        let mut resources = Self::exit(slf)?;
+        let mut error: Option<PyErr> = None;
+        let mut closing = false;
+        Ok(Awaitable::new(
+            move |py: Python<'_>, value: PyResult<PyObject>| {
+                if closing {
+                    closing = false;
+                    match value {
+                        Err(err) if !err.is_instance_of::<PyStopAsyncIteration>(py) => {
+                            error.get_or_insert(err);
+                        }
+                        _ => {}
+                    }
+                }
+                while let Some(resource) = resources.pop() {
+                    let resource = resource.bind(py);
+                    if is_async_generator(resource)? {
+                        closing = true;
+                        let next = resource.call_method0(intern!(py, "__anext__"))?;
+                        return Ok(Step::Await(next.unbind()));
+                    }
+                    if let Err(err) = close_generator(resource) {
+                        error.get_or_insert(err);
+                    }
+                }
+                match error.take() {
+                    Some(err) => Err(err),
+                    None => Ok(Step::Return(false.into_py(py))),
+                }
+            },
+        ))
+    }
+}
+
+impl Scope {
+    /// Leave the scope, returning generators to close
+    fn exit(slf: &Bound<'_, Self>) -> PyResult<Vec<PyObject>> {
+        let py = slf.py();
+        let token = slf.borrow_mut().token.take();
+        if let Some(token) = token {
+            current_scope_var(py)?.call_method1(intern!(py, "reset"), (token,))?;
+        }
+        let mut this = slf.borrow_mut();
+        this.instances.bind(py).clear();
+        Ok(std::mem::take(&mut this.resources))
+    }
+
+    /// Return the innermost entered scope
+    pub(crate) fn current(py: Python<'_>) -> PyResult<Option<Bound<'_, Scope>>> {
+        let scope = current_scope_var(py)?.call_method0(intern!(py, "get"))?;
+        Ok(scope.downcast_into::<Scope>().ok())
+    }
+
+    /// Return instance of the provider cached in the scope
+    pub(crate) fn get(
+        scope: &Bound<'_, Scope>,
+        provider: &Bound<'_, PyAny>,
+    ) -> PyResult<Option<PyObject>> {
+        let instances = scope.borrow().instances.clone_ref(scope.py());
+        Ok(instances
+            .bind(scope.py())
+            .get_item(provider)?
+            .map(Bound::unbind))
+    }
+
+    /// Return the innermost scope with the name
+    pub(crate) fn find<'py>(scope: &Bound<'py, Scope>, name: &str) -> Option<Bound<'py, Scope>> {
+        let py = scope.py();
+        let mut scope = Some(scope.clone());
+        while let Some(current) = scope {
+            if current.borrow().name == name {
+                return Some(current);
+            }
+            scope = current.borrow().parent.as_ref().map(|p| p.bind(py).clone());
+        }
+        None
+    }
+
+    /// Cache instance of the provider
+    pub(crate) fn store(
+        scope: &Bound<'_, Scope>,
+        provider: &Bound<'_, PyAny>,
+        instance: &Bound<'_, PyAny>,
+    ) -> PyResult<()> {
+        scope
+            .borrow()
+            .instances
+            .bind(scope.py())
+            .set_item(provider, instance)
+    }
+
+    /// Register generator closed on scope exit
+    pub(crate) fn add_resource(scope: &Bound<'_, Scope>, resource: &Bound<'_, PyAny>) {
+        scope.borrow_mut().resources.push(resource.clone().unbind());
+    }
+}
+
+pub(crate) fn is_generator(obj: &Bound<'_, PyAny>) -> PyResult<bool> {
+    let py = obj.py();
+    py.import_bound(intern!(py, "inspect"))?
+        .call_method1(intern!(py, "isgenerator"), (obj,))?
+        .is_truthy()
+}
+
+pub(crate) fn is_async_generator(obj: &Bound<'_, PyAny>) -> PyResult<bool> {
+    let py = obj.py();
+    py.import_bound(intern!(py, "inspect"))?
+        .call_method1(intern!(py, "isasyncgen"), (obj,))?
+        .is_truthy()
+}
+
+fn close_generator(generator: &Bound<'_, PyAny>) -> PyResult<()> {
+    let py = generator.py();
+    match generator.call_method0(intern!(py, "__next__")) {
+        Err(err) if !err.is_instance_of::<PyStopIteration>(py) => Err(err),
+        _ => Ok(()),
+    }
+}
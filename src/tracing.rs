@@ -0,0 +1,462 @@
+use crate::containers::DynamicContainer;
+use crate::providers;
+use pyo3::intern;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyModule, PyTuple};
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Hooks observing resolutions of all providers
+static GLOBAL_HOOKS: Mutex<Vec<PyObject>> = Mutex::new(Vec::new());
+/// Hooks observing resolutions of container providers, with weak
+/// references to their containers
+static CONTAINER_HOOKS: Mutex<Vec<(PyObject, PyObject)>> = Mutex::new(Vec::new());
+/// Set when any hook is registered, so tracing is free otherwise
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Container hooks observing the frames being resolved, innermost last
+    static OBSERVED: RefCell<Vec<Vec<PyObject>>> = const { RefCell::new(Vec::new()) };
+    /// Set when provider returns a cached instance
+    static CACHED: Cell<bool> = const { Cell::new(false) };
+}
+
+fn update_enabled() {
+    let enabled = !GLOBAL_HOOKS.lock().map(|h| h.is_empty()).unwrap_or(true)
+        || !CONTAINER_HOOKS.lock().map(|h| h.is_empty()).unwrap_or(true);
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Mark current resolution as served from a cache, e.g. singleton instance
+pub(crate) fn mark_cached() {
+    if ENABLED.load(Ordering::Relaxed) {
+        CACHED.with(|cached| cached.set(true));
+    }
+}
+
+/// Register hook called with `ResolutionEvent` of every provider resolution.
+#[pyfunction]
+pub fn add_resolution_hook(hook: PyObject) {
+    if let Ok(mut hooks) = GLOBAL_HOOKS.lock() {
+        hooks.push(hook);
+    }
+    update_enabled();
+}
+
+/// Unregister hook added with `add_resolution_hook()`.
+#[pyfunction]
+pub fn remove_resolution_hook(py: Python, hook: PyObject) {
+    // Hooks are compared outside the lock, `__eq__` may run any code
+    let registered: Vec<PyObject> = GLOBAL_HOOKS
+        .lock()
+        .map(|hooks| hooks.iter().map(|hook| hook.clone_ref(py)).collect())
+        .unwrap_or_default();
+    let removed = equal_hooks(py, registered.iter(), &hook);
+    if let Ok(mut hooks) = GLOBAL_HOOKS.lock() {
+        hooks.retain(|registered| !removed.iter().any(|removed| removed.is(registered)));
+    }
+    update_enabled();
+}
+
+/// Register hook of the container resolutions.
+///
+/// Hook observes container providers and everything resolved while they
+/// are being resolved. Container is referenced weakly, hooks of collected
+/// containers are dropped.
+pub(crate) fn add_container_hook(container: &Bound<'_, PyAny>, hook: PyObject) -> PyResult<()> {
+    let py = container.py();
+    let container = py
+        .import_bound(intern!(py, "weakref"))?
+        .call_method1(intern!(py, "ref"), (container,))?
+        .unbind();
+    if let Ok(mut hooks) = CONTAINER_HOOKS.lock() {
+        hooks.retain(|(container, _)| is_alive(py, container));
+        hooks.push((container, hook));
+    }
+    update_enabled();
+    Ok(())
+}
+
+/// Unregister hook of the container resolutions
+pub(crate) fn remove_container_hook(py: Python, container: &Bound<'_, PyAny>, hook: &PyObject) {
+    let registered = container_hooks(py);
+    let removed = equal_hooks(
+        py,
+        registered
+            .iter()
+            .filter(|(registered, _)| registered.bind(py).is(container))
+            .map(|(_, hook)| hook),
+        hook,
+    );
+    if let Ok(mut hooks) = CONTAINER_HOOKS.lock() {
+        hooks.retain(|(container, registered)| {
+            is_alive(py, container) && !removed.iter().any(|removed| removed.is(registered))
+        });
+    }
+    update_enabled();
+}
+
+/// Return registered hooks equal to the hook, e.g. the same bound method
+fn equal_hooks<'a>(
+    py: Python,
+    registered: impl Iterator<Item = &'a PyObject>,
+    hook: &PyObject,
+) -> Vec<PyObject> {
+    registered
+        .filter(|registered| registered.bind(py).eq(hook).unwrap_or(false))
+        .map(|registered| registered.clone_ref(py))
+        .collect()
+}
+
+fn is_alive(py: Python, container: &PyObject) -> bool {
+    container
+        .call0(py)
+        .is_ok_and(|container| !container.is_none(py))
+}
+
+/// Return live containers with their hooks
+fn container_hooks(py: Python) -> Vec<(PyObject, PyObject)> {
+    let hooks: Vec<(PyObject, PyObject)> = CONTAINER_HOOKS
+        .lock()
+        .map(|hooks| {
+            hooks
+                .iter()
+                .map(|(container, hook)| (container.clone_ref(py), hook.clone_ref(py)))
+                .collect()
+        })
+        .unwrap_or_default();
+    hooks
+        .into_iter()
+        .filter_map(|(container, hook)| {
+            let container = container.call0(py).ok()?;
+            (!container.is_none(py)).then_some((container, hook))
+        })
+        .collect()
+}
+
+/// Resolution of a provider being traced
+pub(crate) struct Trace {
+    provider: PyObject,
+    depth: usize,
+    start: Instant,
+    hooks: Vec<PyObject>,
+    finished: bool,
+}
+
+impl Trace {
+    /// Start tracing the provider resolution, if any hook is registered
+    pub(crate) fn start(provider: &Bound<'_, PyAny>, depth: usize) -> Option<Self> {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return None;
+        }
+        let py = provider.py();
+        let mut observed: Vec<PyObject> = OBSERVED
+            .with(|observed| observed.borrow().last().cloned())
+            .unwrap_or_default();
+        // Providers are looked up by the name they are registered with
+        let name = providers::Provider::name_of(provider);
+        let container_hooks = match name {
+            Some(_) => container_hooks(py),
+            None => Vec::new(),
+        };
+        for (container, hook) in container_hooks {
+            let contains =
+                container
+                    .bind(py)
+                    .downcast::<DynamicContainer>()
+                    .is_ok_and(|container| {
+                        name.as_ref()
+                            .and_then(|name| container.borrow().providers.get(name).cloned())
+                            .is_some_and(|registered| registered.bind(py).is(provider))
+                    });
+            if contains
+                && !observed
+                    .iter()
+                    .any(|observing| observing.bind(py).is(&hook))
+            {
+                observed.push(hook);
+            }
+        }
+        let mut hooks: Vec<PyObject> = GLOBAL_HOOKS
+            .lock()
+            .map(|hooks| hooks.iter().map(|hook| hook.clone_ref(py)).collect())
+            .unwrap_or_default();
+        hooks.extend(observed.iter().map(|hook| hook.clone_ref(py)));
+        OBSERVED.with(|stack| stack.borrow_mut().push(observed));
+        CACHED.with(|cached| cached.set(false));
+        Some(Self {
+            provider: provider.clone().unbind(),
+            depth,
+            start: Instant::now(),
+            hooks,
+            finished: false,
+        })
+    }
+
+    /// Report resolution outcome to the hooks.
+    ///
+    /// Errors raised by hooks are reported as unraisable, so they never
+    /// change the outcome of the resolution.
+    pub(crate) fn finish(mut self, py: Python, result: &PyResult<PyObject>) {
+        let duration = self.start.elapsed().as_secs_f64();
+        let cached = CACHED.with(|cached| cached.replace(false));
+        self.pop();
+        if self.hooks.is_empty() {
+            return;
+        }
+        let provider = self.provider.bind(py);
+        let event = providers::provider_path(provider).and_then(|path| {
+            Py::new(
+                py,
+                ResolutionEvent {
+                    provider: self.provider.clone_ref(py),
+                    path,
+                    duration,
+                    cached,
+                    error: result
+                        .as_ref()
+                        .err()
+                        .map(|err| err.value_bound(py).clone().into_any().unbind()),
+                    depth: self.depth,
+                },
+            )
+        });
+        let event = match event {
+            Ok(event) => event,
+            Err(err) => return err.write_unraisable_bound(py, Some(provider)),
+        };
+        for hook in &self.hooks {
+            if let Err(err) = hook.call1(py, (&event,)) {
+                err.write_unraisable_bound(py, Some(hook.bind(py)));
+            }
+        }
+    }
+
+    fn pop(&mut self) {
+        if !self.finished {
+            self.finished = true;
+            OBSERVED.with(|stack| stack.borrow_mut().pop());
+        }
+    }
+}
+
+impl Drop for Trace {
+    fn drop(&mut self) {
+        self.pop();
+    }
+}
+
+/// Provider resolution reported to hooks.
+///
+/// Duration covers the call of the provider, for async providers it is
+/// the time to create the awaitable.
+#[pyclass(module = "inj.tracing", frozen)]
+pub struct ResolutionEvent {
+    /// Resolved provider
+    #[pyo3(get)]
+    provider: PyObject,
+    /// Provider path, e.g. ``AppContainer.gateways.database``
+    #[pyo3(get)]
+    path: String,
+    /// Duration in seconds
+    #[pyo3(get)]
+    duration: f64,
+    /// Whether cached instance is returned
+    #[pyo3(get)]
+    cached: bool,
+    /// Raised exception, if resolution has failed
+    #[pyo3(get)]
+    error: Option<PyObject>,
+    /// Number of resolutions the provider is resolved within
+    #[pyo3(get)]
+    depth: usize,
+}
+
+#[pymethods]
+impl ResolutionEvent {
+    fn __repr__(&self) -> String {
+        format!(
+            "<ResolutionEvent {} depth={} duration={:.6}{}{}>",
+            self.path,
+            self.depth,
+            self.duration,
+            if self.cached { " cached" } else { "" },
+            if self.error.is_some() { " failed" } else { "" },
+        )
+    }
+}
+
+/// Hook recording a resolution tree per top-level call.
+///
+/// ```python
+/// recorder = ResolutionRecorder()
+/// container.add_resolution_hook(recorder)
+/// container.service()
+/// print(recorder.format())
+/// ```
+#[pyclass(module = "inj.tracing")]
+#[derive(Default)]
+pub struct ResolutionRecorder {
+    /// Recorded nodes with their depth, children precede their parents
+    nodes: Vec<(usize, Py<PyDict>)>,
+}
+
+#[pymethods]
+impl ResolutionRecorder {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn __call__(&mut self, py: Python, event: &Bound<'_, ResolutionEvent>) -> PyResult<()> {
+        let event = event.get();
+        let split = self
+            .nodes
+            .iter()
+            .rposition(|(depth, _)| *depth <= event.depth)
+            .map_or(0, |position| position + 1);
+        let children = PyList::new_bound(py, self.nodes.drain(split..).map(|(_, node)| node));
+
+        let node = PyDict::new_bound(py);
+        node.set_item("path", &event.path)?;
+        node.set_item("provider", &event.provider)?;
+        node.set_item("duration", event.duration)?;
+        node.set_item("cached", event.cached)?;
+        node.set_item("error", &event.error)?;
+        node.set_item("children", children)?;
+        self.nodes.push((event.depth, node.unbind()));
+        Ok(())
+    }
+
+    /// Return recorded top-level calls.
+    ///
+    /// Every call is a dictionary with path, provider, duration, cached,
+    /// error and children calls.
+    #[getter]
+    fn calls<'py>(&self, py: Python<'py>) -> Bound<'py, PyList> {
+        PyList::new_bound(py, self.nodes.iter().map(|(_, node)| node.clone_ref(py)))
+    }
+
+    /// Remove recorded calls.
+    fn clear(&mut self) {
+        self.nodes.clear();
+    }
+
+    /// Render recorded calls as indented tree.
+    fn format(&self, py: Python) -> PyResult<String> {
+        let mut lines = Vec::new();
+        for (_, node) in &self.nodes {
+            format_node(node.bind(py), 0, &mut lines)?;
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+fn format_node(node: &Bound<'_, PyDict>, level: usize, lines: &mut Vec<String>) -> PyResult<()> {
+    let item = |key: &str| -> PyResult<Bound<'_, PyAny>> {
+        node.get_item(key)?
+            .ok_or_else(|| pyo3::exceptions::PyKeyError::new_err(key.to_owned()))
+    };
+    let mut line = format!(
+        "{}{} {:.3} ms",
+        "  ".repeat(level),
+        item("path")?.extract::<String>()?,
+        item("duration")?.extract::<f64>()? * 1000.0
+    );
+    if item("cached")?.is_truthy()? {
+        line.push_str(" (cached)");
+    }
+    let error = item("error")?;
+    if !error.is_none() {
+        line.push_str(&format!(" failed: {}", error.repr()?));
+    }
+    lines.push(line);
+    for child in item("children")?.iter()? {
+        format_node(child?.downcast()?, level + 1, lines)?;
+    }
+    Ok(())
+}
+
+/// Hook emitting a `logging` record per resolution.
+///
+/// Records carry ``inj_path``, ``inj_duration``, ``inj_cached`` and
+/// ``inj_depth`` extra attributes. Failures are logged at least with
+/// ``WARNING`` level.
+#[pyclass(module = "inj.tracing")]
+pub struct LoggingHook {
+    logger: PyObject,
+    level: i32,
+}
+
+#[pymethods]
+impl LoggingHook {
+    #[new]
+    #[pyo3(signature = (logger=None, level=10))]
+    fn new(py: Python, logger: Option<PyObject>, level: i32) -> PyResult<Self> {
+        let logger = match logger {
+            Some(logger) => logger,
+            None => py
+                .import_bound(intern!(py, "logging"))?
+                .call_method1(intern!(py, "getLogger"), ("inj",))?
+                .unbind(),
+        };
+        Ok(Self { logger, level })
+    }
+
+    fn __call__(&self, py: Python, event: &Bound<'_, ResolutionEvent>) -> PyResult<()> {
+        let event = event.get();
+        let (level, message) = match &event.error {
+            Some(_) => (self.level.max(30), "%s%s failed in %.3f ms: %r"),
+            None if event.cached => (self.level, "%s%s resolved in %.3f ms (cached)"),
+            None => (self.level, "%s%s resolved in %.3f ms"),
+        };
+        let mut args = vec![
+            level.into_py(py),
+            message.into_py(py),
+            "  ".repeat(event.depth).into_py(py),
+            event.path.clone().into_py(py),
+            (event.duration * 1000.0).into_py(py),
+        ];
+        if let Some(error) = &event.error {
+            args.push(error.clone_ref(py));
+        }
+        let extra = PyDict::new_bound(py);
+        extra.set_item("inj_path", &event.path)?;
+        extra.set_item("inj_duration", event.duration)?;
+        extra.set_item("inj_cached", event.cached)?;
+        extra.set_item("inj_depth", event.depth)?;
+        let kwargs = PyDict::new_bound(py);
+        kwargs.set_item("extra", extra)?;
+        self.logger.call_method_bound(
+            py,
+            intern!(py, "log"),
+            PyTuple::new_bound(py, args),
+            Some(&kwargs),
+        )?;
+        Ok(())
+    }
+
+    /// Return logger records are emitted to.
+    #[getter]
+    fn logger(&self) -> PyObject {
+        self.logger.clone()
+    }
+}
+
+pub fn add_tracing_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    let tracing = PyModule::new_bound(py, "inj.tracing")?;
+    tracing.add_class::<ResolutionEvent>()?;
+    tracing.add_class::<ResolutionRecorder>()?;
+    tracing.add_class::<LoggingHook>()?;
+    tracing.add_function(wrap_pyfunction!(add_resolution_hook, &tracing)?)?;
+    tracing.add_function(wrap_pyfunction!(remove_resolution_hook, &tracing)?)?;
+
+    m.add("tracing", &tracing)?;
+    py.import_bound(intern!(py, "sys"))?
+        .getattr(intern!(py, "modules"))?
+        .set_item("inj.tracing", tracing)?;
+    Ok(())
+}
@@ -0,0 +1,1724 @@
+use crate::awaitables::{self, Awaitable, Resume, Step};
+use crate::containers::DynamicContainer;
+use crate::{errors, providers};
+use pyo3::exceptions::PyStopAsyncIteration;
+use pyo3::intern;
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+use pyo3::types::{PyDict, PyList, PyModule, PyString, PyTuple, PyType};
+use std::collections::{HashMap, VecDeque};
+
+/// Base class of injection markers.
+///
+/// Provider may be given as a dotted string path, which is resolved against
+/// the container at wire time, e.g. ``Provide["services.user_repo"]``.
+/// Optional modifier is applied to the injected value, e.g.
+/// ``Provide["config.timeout", as_int()]``.
+#[pyclass(module = "inj.wiring", name = "_Marker", subclass)]
+pub struct Marker {
+    #[pyo3(get)]
+    pub provider: PyObject,
+    #[pyo3(get)]
+    pub modifier: Option<PyObject>,
+}
+
+#[pymethods]
+impl Marker {
+    #[new]
+    #[pyo3(signature = (provider, modifier=None))]
+    fn new(provider: PyObject, modifier: Option<PyObject>) -> Self {
+        Self { provider, modifier }
+    }
+
+    #[classmethod]
+    fn __class_getitem__<'py>(
+        cls: &Bound<'py, PyType>,
+        item: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        match item.downcast::<PyTuple>() {
+            Ok(item) => cls.call1(item),
+            Err(_) => cls.call1((item,)),
+        }
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let provider = this.provider.bind(py).repr()?;
+        Ok(match &this.modifier {
+            Some(modifier) => format!(
+                "{}[{}, {}]",
+                slf.get_type().qualname()?,
+                provider,
+                modifier.bind(py).repr()?
+            ),
+            None => format!("{}[{}]", slf.get_type().qualname()?, provider),
+        })
+    }
+}
+
+/// Modifier converting injected value with a type, e.g. ``as_int()``
+#[pyclass(module = "inj.wiring")]
+pub struct TypeModifier {
+    #[pyo3(get)]
+    type_: PyObject,
+}
+
+#[pymethods]
+impl TypeModifier {
+    #[new]
+    fn new(type_: PyObject) -> Self {
+        Self { type_ }
+    }
+
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!("as_({})", self.type_.bind(py).repr()?))
+    }
+}
+
+/// Return modifier converting injected value to ``int``.
+#[pyfunction]
+pub fn as_int(py: Python) -> TypeModifier {
+    TypeModifier::new(
+        py.get_type_bound::<pyo3::types::PyLong>()
+            .into_any()
+            .unbind(),
+    )
+}
+
+/// Return modifier converting injected value to ``float``.
+#[pyfunction]
+pub fn as_float(py: Python) -> TypeModifier {
+    TypeModifier::new(
+        py.get_type_bound::<pyo3::types::PyFloat>()
+            .into_any()
+            .unbind(),
+    )
+}
+
+/// Return modifier converting injected value with the type.
+#[pyfunction]
+pub fn as_(type_: PyObject) -> TypeModifier {
+    TypeModifier::new(type_)
+}
+
+#[derive(Clone)]
+enum Segment {
+    Attribute(Py<PyString>),
+    Item(PyObject),
+    Call(Py<PyTuple>, Option<Py<PyDict>>),
+}
+
+/// Modifier injecting attribute, item or call result of the provided value.
+///
+/// ```python
+/// Provide["service", provided().settings["timeout"]]
+/// Provide["service", provided().get_client.call()]
+/// ```
+#[pyclass(module = "inj.wiring")]
+#[derive(Clone, Default)]
+pub struct ProvidedInstance {
+    segments: Vec<Segment>,
+}
+
+#[pymethods]
+impl ProvidedInstance {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn __getattr__(&self, name: Bound<'_, PyString>) -> PyResult<Self> {
+        if name.to_str()?.starts_with("__") {
+            return Err(pyo3::exceptions::PyAttributeError::new_err(name.unbind()));
+        }
+        Ok(self.with(Segment::Attribute(name.unbind())))
+    }
+
+    fn __getitem__(&self, item: PyObject) -> Self {
+        self.with(Segment::Item(item))
+    }
+
+    #[pyo3(signature = (*args, **kwargs))]
+    fn call(&self, args: &Bound<'_, PyTuple>, kwargs: Option<&Bound<'_, PyDict>>) -> Self {
+        self.with(Segment::Call(
+            args.clone().unbind(),
+            kwargs.map(|kwargs| kwargs.clone().unbind()),
+        ))
+    }
+
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        let mut repr = String::from("provided()");
+        for segment in &self.segments {
+            match segment {
+                Segment::Attribute(name) => repr.push_str(&format!(".{name}")),
+                Segment::Item(item) => repr.push_str(&format!("[{}]", item.bind(py).repr()?)),
+                Segment::Call(..) => repr.push_str(".call()"),
+            }
+        }
+        Ok(repr)
+    }
+}
+
+impl ProvidedInstance {
+    fn with(&self, segment: Segment) -> Self {
+        let mut segments = self.segments.clone();
+        segments.push(segment);
+        Self { segments }
+    }
+}
+
+/// Return modifier injecting attributes, items or call results of the
+/// provided value.
+#[pyfunction]
+pub fn provided() -> ProvidedInstance {
+    ProvidedInstance::new()
+}
+
+/// Modifier of injected value
+#[derive(Clone)]
+enum Modifier {
+    Type(PyObject),
+    Provided(Vec<Segment>),
+}
+
+impl Modifier {
+    fn new(modifier: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if let Ok(modifier) = modifier.downcast::<TypeModifier>() {
+            return Ok(Self::Type(modifier.borrow().type_.clone_ref(modifier.py())));
+        }
+        if let Ok(modifier) = modifier.downcast::<ProvidedInstance>() {
+            return Ok(Self::Provided(modifier.borrow().segments.clone()));
+        }
+        Err(pyo3::exceptions::PyTypeError::new_err(format!(
+            "Unknown modifier {}",
+            modifier.repr()?
+        )))
+    }
+
+    fn apply(&self, py: Python, value: PyObject) -> PyResult<PyObject> {
+        match self {
+            Self::Type(type_) => type_.call1(py, (value,)),
+            Self::Provided(segments) => {
+                let mut value = value.into_bound(py);
+                for segment in segments {
+                    value = match segment {
+                        Segment::Attribute(name) => value.getattr(name.bind(py))?,
+                        Segment::Item(item) => value.get_item(item.bind(py))?,
+                        Segment::Call(args, kwargs) => {
+                            value.call(args.bind(py), kwargs.as_ref().map(|k| k.bind(py)))?
+                        }
+                    };
+                }
+                Ok(value.unbind())
+            }
+        }
+    }
+}
+
+/// Marker of provider result injection.
+///
+/// ```python
+/// @inject
+/// def handler(service: Service = Provide[Container.service]): ...
+/// ```
+#[pyclass(extends=Marker, module="inj.wiring", subclass)]
+pub struct Provide;
+
+#[pymethods]
+impl Provide {
+    #[new]
+    #[pyo3(signature = (provider, modifier=None))]
+    fn new(provider: PyObject, modifier: Option<PyObject>) -> (Self, Marker) {
+        (Self, Marker::new(provider, modifier))
+    }
+}
+
+/// Marker of provider injection.
+///
+/// ```python
+/// @inject
+/// def handler(factory: Callable[..., Service] = Provider[Container.service]): ...
+/// ```
+#[pyclass(extends=Marker, module="inj.wiring", name="Provider", subclass)]
+pub struct ProviderMarker;
+
+#[pymethods]
+impl ProviderMarker {
+    #[new]
+    #[pyo3(signature = (provider, modifier=None))]
+    fn new(provider: PyObject, modifier: Option<PyObject>) -> (Self, Marker) {
+        (Self, Marker::new(provider, modifier))
+    }
+}
+
+/// Marker of resource injection with shutdown after the call.
+///
+/// ```python
+/// @inject
+/// def handler(db: Database = Closing[Provide[Container.db]]): ...
+/// ```
+#[pyclass(extends=Marker, module="inj.wiring", subclass)]
+pub struct Closing;
+
+#[pymethods]
+impl Closing {
+    #[new]
+    #[pyo3(signature = (provider, modifier=None))]
+    fn new(provider: PyObject, modifier: Option<PyObject>) -> (Self, Marker) {
+        (Self, Marker::new(provider, modifier))
+    }
+}
+
+/// Injection of a single function parameter
+#[derive(Clone)]
+struct Injection {
+    name: Py<PyString>,
+    position: Option<usize>,
+    /// Provider referenced by the marker, or its dotted path
+    original: PyObject,
+    /// Inject the provider itself instead of its result
+    is_provider: bool,
+    modifier: Option<Modifier>,
+    closing: bool,
+    provider: Option<PyObject>,
+}
+
+impl Injection {
+    /// Create injection from a parameter or attribute marker, unwrapping `Closing`
+    fn new(
+        name: Py<PyString>,
+        position: Option<usize>,
+        marker: &Bound<'_, Marker>,
+    ) -> PyResult<Self> {
+        let py = marker.py();
+        let closing = marker.is_instance_of::<Closing>();
+        let mut marker = marker.clone();
+        if closing {
+            let inner = marker.borrow().provider.clone_ref(py).into_bound(py);
+            if let Ok(inner) = inner.downcast_into::<Marker>() {
+                marker = inner;
+            }
+        }
+        let (original, modifier) = {
+            let marker = marker.borrow();
+            let modifier = match &marker.modifier {
+                Some(modifier) if !modifier.is_none(py) => Some(Modifier::new(modifier.bind(py))?),
+                _ => None,
+            };
+            (marker.provider.clone_ref(py), modifier)
+        };
+        let is_provider = marker.is_instance_of::<ProviderMarker>();
+        if is_provider && modifier.is_some() {
+            return Err(pyo3::exceptions::PyTypeError::new_err(
+                "Modifiers are supported only by Provide markers",
+            ));
+        }
+        Ok(Self {
+            name,
+            position,
+            original,
+            is_provider,
+            modifier,
+            closing,
+            provider: None,
+        })
+    }
+}
+
+impl Injection {
+    /// Bind injection to provider of the container.
+    ///
+    /// String markers must resolve, otherwise error names the module, owner
+    /// and injected parameter or attribute.
+    fn bind(
+        &mut self,
+        py: Python,
+        providers_map: &ProvidersMap,
+        kind: &str,
+        owner: &str,
+        module: &str,
+    ) -> PyResult<()> {
+        let original = self.original.bind(py);
+        self.provider = match original.downcast::<PyString>() {
+            Ok(path) => match providers_map.resolve_path(py, path.to_str()?) {
+                Ok(provider) => Some(provider),
+                Err(err) => {
+                    let error = errors::Error::new_err(format!(
+                        "Unable to resolve provider \"{}\" for {} \"{}\" of \"{}\" in module \"{}\"",
+                        path,
+                        kind,
+                        self.name.bind(py),
+                        owner,
+                        module,
+                    ));
+                    error.set_cause(py, Some(err));
+                    return Err(error);
+                }
+            },
+            Err(_) => providers_map.resolve_provider(py, original)?,
+        };
+        Ok(())
+    }
+
+    /// Return injected value of the bound provider
+    fn resolve(&self, py: Python, provider: &PyObject) -> PyResult<PyObject> {
+        if self.is_provider {
+            return Ok(provider.clone_ref(py));
+        }
+        let value = provider.call0(py)?;
+        match &self.modifier {
+            Some(modifier) if !awaitables::is_awaitable(value.bind(py)) => {
+                modifier.apply(py, value)
+            }
+            _ => Ok(value),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionKind {
+    Sync,
+    Coroutine,
+    Generator,
+    AsyncGenerator,
+}
+
+impl FunctionKind {
+    fn of(func: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let py = func.py();
+        let inspect = py.import_bound(intern!(py, "inspect"))?;
+        let is = |name: &Bound<'_, PyString>| -> PyResult<bool> {
+            inspect.call_method1(name, (func,))?.is_truthy()
+        };
+        Ok(if is(intern!(py, "iscoroutinefunction"))? {
+            Self::Coroutine
+        } else if is(intern!(py, "isasyncgenfunction"))? {
+            Self::AsyncGenerator
+        } else if is(intern!(py, "isgeneratorfunction"))? {
+            Self::Generator
+        } else {
+            Self::Sync
+        })
+    }
+}
+
+/// Slot of the call, which receives injected value
+enum Target {
+    Keyword(Py<PyString>),
+    Position(usize),
+}
+
+/// Injected value, which is awaited before the call
+struct Deferred {
+    target: Target,
+    modifier: Option<Modifier>,
+}
+
+impl Deferred {
+    /// Set awaited value as call argument
+    fn inject(
+        self,
+        args: &Bound<'_, PyList>,
+        kwargs: &Bound<'_, PyDict>,
+        value: PyObject,
+    ) -> PyResult<()> {
+        let value = match &self.modifier {
+            Some(modifier) => modifier.apply(kwargs.py(), value)?,
+            None => value,
+        };
+        match self.target {
+            Target::Keyword(name) => kwargs.set_item(name, value),
+            Target::Position(position) => args.set_item(position, value),
+        }
+    }
+}
+
+/// Call arguments with injected values
+struct PreparedCall {
+    args: Py<PyList>,
+    kwargs: Py<PyDict>,
+    /// Injected awaitables, to be awaited before the call
+    awaiting: VecDeque<(Deferred, PyObject)>,
+    /// Resources to shut down after the call
+    closing: VecDeque<PyObject>,
+}
+
+/// Function patched by `@inject`.
+///
+/// Marker defaults are replaced with provider results once the function's
+/// module is wired to a container. Injected values are resolved on every
+/// call, arguments passed by the caller take precedence.
+#[pyclass(module = "inj.wiring", dict)]
+pub struct InjectedFunction {
+    func: PyObject,
+    kind: FunctionKind,
+    injections: Vec<Injection>,
+    /// Defaults of positional-only parameters, injected positionally
+    positional_only: Vec<Option<PyObject>>,
+}
+
+#[pymethods]
+impl InjectedFunction {
+    #[pyo3(signature = (*args, **kwargs))]
+    fn __call__(
+        slf: &Bound<'_, Self>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        // Providers may rewire the function while being resolved
+        let (func, kind, injections, positional_only) = {
+            let this = slf.borrow();
+            (
+                this.func.clone_ref(py),
+                this.kind,
+                this.injections.clone(),
+                this.positional_only.clone(),
+            )
+        };
+        let is_async = matches!(kind, FunctionKind::Coroutine | FunctionKind::AsyncGenerator);
+        let call = Self::prepare(py, &injections, &positional_only, args, kwargs, is_async)?;
+
+        match kind {
+            FunctionKind::Sync => {
+                let result = func.call_bound(
+                    py,
+                    PyTuple::new_bound(py, call.args.bind(py)),
+                    Some(call.kwargs.bind(py)),
+                );
+                shutdown_resources(py, call.closing, result)
+            }
+            FunctionKind::Generator => {
+                match func.call_bound(
+                    py,
+                    PyTuple::new_bound(py, call.args.bind(py)),
+                    Some(call.kwargs.bind(py)),
+                ) {
+                    Ok(generator) => Ok(Bound::new(
+                        py,
+                        InjectedGenerator {
+                            generator,
+                            closing: call.closing,
+                            finished: false,
+                        },
+                    )?
+                    .into_any()
+                    .unbind()),
+                    Err(err) => shutdown_resources(py, call.closing, Err(err)),
+                }
+            }
+            FunctionKind::Coroutine => {
+                let coroutine = CoroutineCall {
+                    func,
+                    args: call.args,
+                    kwargs: call.kwargs,
+                    awaiting: call.awaiting,
+                    current: None,
+                    stage: CallStage::Inject,
+                    closer: Closer::new(call.closing),
+                };
+                Ok(Bound::new(py, Awaitable::new(coroutine))?
+                    .into_any()
+                    .unbind())
+            }
+            FunctionKind::AsyncGenerator => Ok(Bound::new(
+                py,
+                InjectedAsyncGenerator {
+                    state: AsyncGeneratorState::Pending {
+                        func,
+                        args: call.args,
+                        kwargs: call.kwargs,
+                        awaiting: call.awaiting,
+                    },
+                    closing: call.closing,
+                },
+            )?
+            .into_any()
+            .unbind()),
+        }
+    }
+
+    fn __get__(
+        slf: &Bound<'_, Self>,
+        instance: Option<&Bound<'_, PyAny>>,
+        _owner: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        match instance {
+            Some(instance) if !instance.is_none() => Ok(py
+                .import_bound(intern!(py, "types"))?
+                .getattr(intern!(py, "MethodType"))?
+                .call1((slf, instance))?
+                .unbind()),
+            _ => Ok(slf.clone().into_any().unbind()),
+        }
+    }
+
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!("<injected {}>", self.func.bind(py).repr()?))
+    }
+}
+
+impl InjectedFunction {
+    /// Bind injections to providers of the container.
+    ///
+    /// String markers must resolve, otherwise error names the module,
+    /// function and parameter.
+    fn bind(&mut self, py: Python, providers_map: &ProvidersMap, module: &str) -> PyResult<()> {
+        let owner = self
+            .func
+            .bind(py)
+            .getattr(intern!(py, "__qualname__"))?
+            .to_string();
+        for injection in self.injections.iter_mut() {
+            injection.bind(py, providers_map, "parameter", &owner, module)?;
+        }
+        Ok(())
+    }
+
+    /// Drop bound providers, so marker defaults are used again
+    fn unbind(&mut self) {
+        for injection in self.injections.iter_mut() {
+            injection.provider = None;
+        }
+    }
+
+    /// Resolve injections, which are not passed by the caller.
+    ///
+    /// Marker passed explicitly is injected as well. Positional-only
+    /// parameters are injected as positional arguments, parameters skipped
+    /// between them take their defaults. With `defer_awaitables` awaitable
+    /// values are left for the caller to await.
+    fn prepare(
+        py: Python,
+        injections: &[Injection],
+        positional_only: &[Option<PyObject>],
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+        defer_awaitables: bool,
+    ) -> PyResult<PreparedCall> {
+        let call_args = PyList::new_bound(py, args);
+        let call_kwargs = match kwargs {
+            Some(kwargs) => kwargs.copy()?,
+            None => PyDict::new_bound(py),
+        };
+        let mut awaiting = VecDeque::new();
+        let mut closing = VecDeque::new();
+
+        // Fill positional-only parameters up to the last injected one, unless
+        // a required parameter is missing and the call fails anyway
+        let injected_end = injections
+            .iter()
+            .filter(|injection| injection.provider.is_some())
+            .filter_map(|injection| injection.position)
+            .filter(|position| *position < positional_only.len())
+            .map(|position| position + 1)
+            .max()
+            .unwrap_or(0);
+        if injected_end > args.len() {
+            let defaults = &positional_only[args.len()..injected_end];
+            if defaults.iter().all(Option::is_some) {
+                for default in defaults.iter().flatten() {
+                    call_args.append(default)?;
+                }
+            }
+        }
+
+        for injection in injections {
+            let Some(provider) = &injection.provider else {
+                continue;
+            };
+            let target = match injection.position {
+                Some(position) if position < args.len() => continue,
+                Some(position) if position < positional_only.len() => {
+                    if position >= call_args.len() {
+                        continue;
+                    }
+                    Target::Position(position)
+                }
+                _ => {
+                    let name = injection.name.bind(py);
+                    if let Some(passed) = call_kwargs.get_item(name)? {
+                        if !passed.is_instance_of::<Marker>() {
+                            continue;
+                        }
+                    }
+                    Target::Keyword(injection.name.clone_ref(py))
+                }
+            };
+
+            let value = injection.resolve(py, provider)?;
+            if defer_awaitables && awaitables::is_awaitable(value.bind(py)) {
+                let deferred = Deferred {
+                    target,
+                    modifier: injection.modifier.clone(),
+                };
+                awaiting.push_back((deferred, value));
+            } else {
+                match target {
+                    Target::Keyword(name) => call_kwargs.set_item(name, value)?,
+                    Target::Position(position) => call_args.set_item(position, value)?,
+                }
+            }
+            if injection.closing && provider.bind(py).is_instance_of::<providers::Resource>() {
+                closing.push_back(provider.clone_ref(py));
+            }
+        }
+        Ok(PreparedCall {
+            args: call_args.unbind(),
+            kwargs: call_kwargs.unbind(),
+            awaiting,
+            closing,
+        })
+    }
+}
+
+/// Shutdown closing resources synchronously and return outcome of the call
+fn shutdown_resources(
+    py: Python,
+    resources: VecDeque<PyObject>,
+    outcome: PyResult<PyObject>,
+) -> PyResult<PyObject> {
+    let mut outcome = outcome;
+    for resource in resources {
+        if let Err(err) = resource.call_method0(py, intern!(py, "shutdown")) {
+            if outcome.is_ok() {
+                outcome = Err(err);
+            }
+        }
+    }
+    outcome
+}
+
+/// Shutdown of closing resources in async calls.
+///
+/// Awaits shutdowns one by one and finishes with the outcome of the call.
+/// Shutdown error is raised only if the call succeeded.
+struct Closer {
+    resources: VecDeque<PyObject>,
+    outcome: Option<PyResult<PyObject>>,
+    awaiting: bool,
+}
+
+impl Closer {
+    fn new(resources: VecDeque<PyObject>) -> Self {
+        Self {
+            resources,
+            outcome: None,
+            awaiting: false,
+        }
+    }
+
+    fn start(&mut self, py: Python, outcome: PyResult<PyObject>) -> PyResult<Step> {
+        self.outcome = Some(outcome);
+        self.resume(py, Ok(py.None()))
+    }
+
+    fn resume(&mut self, py: Python, value: PyResult<PyObject>) -> PyResult<Step> {
+        if std::mem::take(&mut self.awaiting) {
+            if let Err(err) = value {
+                self.fail(err);
+            }
+        }
+        while let Some(resource) = self.resources.pop_front() {
+            match resource.call_method0(py, intern!(py, "shutdown")) {
+                Ok(result) if awaitables::is_awaitable(result.bind(py)) => {
+                    self.awaiting = true;
+                    return Ok(Step::Await(result));
+                }
+                Ok(_) => {}
+                Err(err) => self.fail(err),
+            }
+        }
+        self.outcome
+            .take()
+            .unwrap_or_else(|| Ok(py.None()))
+            .map(Step::Return)
+    }
+
+    fn fail(&mut self, err: PyErr) {
+        if !matches!(self.outcome, Some(Err(_))) {
+            self.outcome = Some(Err(err));
+        }
+    }
+}
+
+enum CallStage {
+    Inject,
+    Call,
+    Close,
+}
+
+/// Call of injected coroutine function.
+///
+/// Awaits injected awaitables, then the coroutine itself and finally
+/// shutdowns of closing resources.
+struct CoroutineCall {
+    func: PyObject,
+    args: Py<PyList>,
+    kwargs: Py<PyDict>,
+    awaiting: VecDeque<(Deferred, PyObject)>,
+    current: Option<Deferred>,
+    stage: CallStage,
+    closer: Closer,
+}
+
+impl Resume for CoroutineCall {
+    fn resume(&mut self, py: Python<'_>, value: PyResult<PyObject>) -> PyResult<Step> {
+        match self.stage {
+            CallStage::Inject => {
+                let value = match value {
+                    Ok(value) => value,
+                    Err(err) => {
+                        self.stage = CallStage::Close;
+                        return self.closer.start(py, Err(err));
+                    }
+                };
+                if let Some(deferred) = self.current.take() {
+                    deferred.inject(self.args.bind(py), self.kwargs.bind(py), value)?;
+                }
+                if let Some((deferred, awaitable)) = self.awaiting.pop_front() {
+                    self.current = Some(deferred);
+                    return Ok(Step::Await(awaitable));
+                }
+                let args = PyTuple::new_bound(py, self.args.bind(py));
+                match self.func.call_bound(py, args, Some(self.kwargs.bind(py))) {
+                    Ok(coroutine) => {
+                        self.stage = CallStage::Call;
+                        Ok(Step::Await(coroutine))
+                    }
+                    Err(err) => {
+                        self.stage = CallStage::Close;
+                        self.closer.start(py, Err(err))
+                    }
+                }
+            }
+            CallStage::Call => {
+                self.stage = CallStage::Close;
+                self.closer.start(py, value)
+            }
+            CallStage::Close => self.closer.resume(py, value),
+        }
+    }
+}
+
+/// Generator returned by injected generator function.
+///
+/// Closing resources are shut down when the generator is exhausted, fails
+/// or is closed.
+#[pyclass(module = "inj.wiring")]
+pub struct InjectedGenerator {
+    generator: PyObject,
+    closing: VecDeque<PyObject>,
+    finished: bool,
+}
+
+#[pymethods]
+impl InjectedGenerator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python) -> PyResult<PyObject> {
+        self.send(py, py.None())
+    }
+
+    fn send(&mut self, py: Python, value: PyObject) -> PyResult<PyObject> {
+        let result = self
+            .generator
+            .call_method1(py, intern!(py, "send"), (value,));
+        self.finish_on_error(py, result)
+    }
+
+    #[pyo3(signature = (*args))]
+    fn throw(&mut self, py: Python, args: &Bound<'_, PyTuple>) -> PyResult<PyObject> {
+        let result = self
+            .generator
+            .bind(py)
+            .call_method1(intern!(py, "throw"), args)
+            .map(Bound::unbind);
+        self.finish_on_error(py, result)
+    }
+
+    fn close(&mut self, py: Python) -> PyResult<()> {
+        let result = self
+            .generator
+            .call_method0(py, intern!(py, "close"))
+            .map(|_| py.None());
+        self.finish(py, result).map(|_| ())
+    }
+}
+
+impl InjectedGenerator {
+    fn finish_on_error(&mut self, py: Python, result: PyResult<PyObject>) -> PyResult<PyObject> {
+        match result {
+            Ok(value) => Ok(value),
+            Err(err) => self.finish(py, Err(err)),
+        }
+    }
+
+    fn finish(&mut self, py: Python, outcome: PyResult<PyObject>) -> PyResult<PyObject> {
+        if std::mem::replace(&mut self.finished, true) {
+            return outcome;
+        }
+        shutdown_resources(py, std::mem::take(&mut self.closing), outcome)
+    }
+}
+
+enum AsyncGeneratorState {
+    /// Injected awaitables are not awaited and generator is not created yet
+    Pending {
+        func: PyObject,
+        args: Py<PyList>,
+        kwargs: Py<PyDict>,
+        awaiting: VecDeque<(Deferred, PyObject)>,
+    },
+    Running(PyObject),
+    Finished,
+}
+
+/// Async generator returned by injected async generator function.
+///
+/// Injected awaitables are awaited on the first step. Closing resources are
+/// shut down when the generator is exhausted, fails or is closed.
+#[pyclass(module = "inj.wiring")]
+pub struct InjectedAsyncGenerator {
+    state: AsyncGeneratorState,
+    closing: VecDeque<PyObject>,
+}
+
+enum AsyncGeneratorInput {
+    Send(PyObject),
+    Throw(Py<PyTuple>),
+    Close,
+}
+
+#[pymethods]
+impl InjectedAsyncGenerator {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __anext__(slf: &Bound<'_, Self>) -> PyResult<Py<Awaitable>> {
+        let py = slf.py();
+        Self::step(slf, AsyncGeneratorInput::Send(py.None()))
+    }
+
+    fn asend(slf: &Bound<'_, Self>, value: PyObject) -> PyResult<Py<Awaitable>> {
+        Self::step(slf, AsyncGeneratorInput::Send(value))
+    }
+
+    #[pyo3(signature = (*args))]
+    fn athrow(slf: &Bound<'_, Self>, args: &Bound<'_, PyTuple>) -> PyResult<Py<Awaitable>> {
+        Self::step(slf, AsyncGeneratorInput::Throw(args.clone().unbind()))
+    }
+
+    fn aclose(slf: &Bound<'_, Self>) -> PyResult<Py<Awaitable>> {
+        Self::step(slf, AsyncGeneratorInput::Close)
+    }
+}
+
+impl InjectedAsyncGenerator {
+    fn step(slf: &Bound<'_, Self>, input: AsyncGeneratorInput) -> PyResult<Py<Awaitable>> {
+        let step = AsyncGeneratorStep {
+            generator: slf.clone().unbind(),
+            input: Some(input),
+            current: None,
+            stage: CallStage::Inject,
+            closer: Closer::new(VecDeque::new()),
+        };
+        Py::new(slf.py(), Awaitable::new(step))
+    }
+}
+
+/// Single step of injected async generator
+struct AsyncGeneratorStep {
+    generator: Py<InjectedAsyncGenerator>,
+    input: Option<AsyncGeneratorInput>,
+    current: Option<Deferred>,
+    stage: CallStage,
+    closer: Closer,
+}
+
+impl AsyncGeneratorStep {
+    /// Mark generator finished and shutdown its closing resources
+    fn finish(&mut self, py: Python, outcome: PyResult<PyObject>) -> PyResult<Step> {
+        let mut generator = self.generator.bind(py).borrow_mut();
+        generator.state = AsyncGeneratorState::Finished;
+        self.closer.resources = std::mem::take(&mut generator.closing);
+        drop(generator);
+        self.stage = CallStage::Close;
+        self.closer.start(py, outcome)
+    }
+
+    /// Await injected awaitables and create the generator
+    fn inject(&mut self, py: Python, value: PyResult<PyObject>) -> PyResult<Option<Step>> {
+        let value = match value {
+            Ok(value) => value,
+            Err(err) => return self.finish(py, Err(err)).map(Some),
+        };
+        let mut generator = self.generator.bind(py).borrow_mut();
+        let created = match &mut generator.state {
+            AsyncGeneratorState::Pending {
+                func,
+                args,
+                kwargs,
+                awaiting,
+            } => {
+                if let Some(deferred) = self.current.take() {
+                    deferred.inject(args.bind(py), kwargs.bind(py), value)?;
+                }
+                if let Some((deferred, awaitable)) = awaiting.pop_front() {
+                    self.current = Some(deferred);
+                    return Ok(Some(Step::Await(awaitable)));
+                }
+                let args = PyTuple::new_bound(py, args.bind(py));
+                func.call_bound(py, args, Some(kwargs.bind(py)))
+            }
+            _ => return Ok(None),
+        };
+        drop(generator);
+        match created {
+            Ok(created) => {
+                self.generator.bind(py).borrow_mut().state = AsyncGeneratorState::Running(created);
+                Ok(None)
+            }
+            Err(err) => self.finish(py, Err(err)).map(Some),
+        }
+    }
+}
+
+impl Resume for AsyncGeneratorStep {
+    fn resume(&mut self, py: Python<'_>, value: PyResult<PyObject>) -> PyResult<Step> {
+        match self.stage {
+            CallStage::Inject => {
+                let is_closing = matches!(self.input, Some(AsyncGeneratorInput::Close));
+                let is_pending = matches!(
+                    self.generator.bind(py).borrow().state,
+                    AsyncGeneratorState::Pending { .. }
+                );
+                if is_closing && is_pending {
+                    return self.finish(py, Ok(py.None()));
+                }
+                if let Some(step) = self.inject(py, value)? {
+                    return Ok(step);
+                }
+
+                let running = match &self.generator.bind(py).borrow().state {
+                    AsyncGeneratorState::Running(running) => Some(running.clone_ref(py)),
+                    _ => None,
+                };
+                let input = self.input.take();
+                let Some(running) = running else {
+                    return match input {
+                        Some(AsyncGeneratorInput::Throw(args)) => {
+                            Err(throw_args_error(args.bind(py))?)
+                        }
+                        Some(AsyncGeneratorInput::Close) => Ok(Step::Return(py.None())),
+                        _ => Err(PyStopAsyncIteration::new_err(())),
+                    };
+                };
+                let running = running.bind(py);
+                let awaitable = match input {
+                    Some(AsyncGeneratorInput::Send(value)) => {
+                        running.call_method1(intern!(py, "asend"), (value,))
+                    }
+                    Some(AsyncGeneratorInput::Throw(args)) => {
+                        running.call_method1(intern!(py, "athrow"), args.bind(py))
+                    }
+                    Some(AsyncGeneratorInput::Close) | None => {
+                        self.input = Some(AsyncGeneratorInput::Close);
+                        running.call_method0(intern!(py, "aclose"))
+                    }
+                };
+                match awaitable {
+                    Ok(awaitable) => {
+                        self.stage = CallStage::Call;
+                        Ok(Step::Await(awaitable.unbind()))
+                    }
+                    Err(err) => self.finish(py, Err(err)),
+                }
+            }
+            CallStage::Call => match value {
+                Ok(value) if !matches!(self.input, Some(AsyncGeneratorInput::Close)) => {
+                    Ok(Step::Return(value))
+                }
+                outcome => self.finish(py, outcome.map(|_| py.None())),
+            },
+            CallStage::Close => self.closer.resume(py, value),
+        }
+    }
+}
+
+/// Build error thrown into finished generator from `throw()` arguments
+fn throw_args_error(args: &Bound<'_, PyTuple>) -> PyResult<PyErr> {
+    let typ = args.get_item(0)?;
+    if !typ.is_instance_of::<PyType>() {
+        return Ok(PyErr::from_value_bound(typ));
+    }
+    match args.get_item(1) {
+        Ok(value) if value.is_instance(&typ)? => Ok(PyErr::from_value_bound(value)),
+        Ok(value) if !value.is_none() => Ok(PyErr::from_value_bound(typ.call1((value,))?)),
+        _ => Ok(PyErr::from_value_bound(typ.call0()?)),
+    }
+}
+
+/// Return marker of the parameter.
+///
+/// Marker inside `typing.Annotated` metadata takes precedence over marker
+/// default value.
+fn parameter_marker<'py>(
+    parameter: &Bound<'py, PyAny>,
+    typing: &Bound<'py, PyModule>,
+) -> PyResult<Option<Bound<'py, Marker>>> {
+    let py = parameter.py();
+    let annotation = parameter.getattr(intern!(py, "annotation"))?;
+    let origin = typing.call_method1(intern!(py, "get_origin"), (&annotation,))?;
+    if origin.is(&typing.getattr(intern!(py, "Annotated"))?) {
+        let metadata = typing.call_method1(intern!(py, "get_args"), (&annotation,))?;
+        for item in metadata.iter()?.skip(1) {
+            if let Ok(marker) = item?.downcast_into::<Marker>() {
+                return Ok(Some(marker));
+            }
+        }
+    }
+    Ok(parameter
+        .getattr(intern!(py, "default"))?
+        .downcast_into::<Marker>()
+        .ok())
+}
+
+/// Decorate function with dependency injection.
+///
+/// Works with functions, coroutine functions, generator functions and
+/// async generator functions.
+#[pyfunction]
+pub fn inject<'py>(func: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+    let py = func.py();
+    if func.is_instance_of::<InjectedFunction>() {
+        return Ok(func.clone());
+    }
+
+    let inspect = py.import_bound(intern!(py, "inspect"))?;
+    let typing = py.import_bound(intern!(py, "typing"))?;
+    let parameter_type = inspect.getattr(intern!(py, "Parameter"))?;
+    let positional_only_kind = parameter_type.getattr(intern!(py, "POSITIONAL_ONLY"))?;
+    let positional_kinds = [
+        positional_only_kind.clone(),
+        parameter_type.getattr(intern!(py, "POSITIONAL_OR_KEYWORD"))?,
+    ];
+    let empty = parameter_type.getattr(intern!(py, "empty"))?;
+    let parameters = inspect
+        .call_method1(intern!(py, "signature"), (func,))?
+        .getattr(intern!(py, "parameters"))?;
+
+    let mut injections = Vec::new();
+    let mut positional_only = Vec::new();
+    for (position, parameter) in parameters.call_method0("values")?.iter()?.enumerate() {
+        let parameter = parameter?;
+        if parameter
+            .getattr(intern!(py, "kind"))?
+            .eq(&positional_only_kind)?
+        {
+            let default = parameter.getattr(intern!(py, "default"))?;
+            positional_only.push((!default.is(&empty)).then(|| default.unbind()));
+        }
+        let Some(marker) = parameter_marker(&parameter, &typing)? else {
+            continue;
+        };
+        let kind = parameter.getattr(intern!(py, "kind"))?;
+        let is_positional = positional_kinds
+            .iter()
+            .any(|positional_kind| kind.eq(positional_kind).unwrap_or(false));
+        let name = parameter
+            .getattr(intern!(py, "name"))?
+            .downcast_into::<PyString>()?
+            .unbind();
+        injections.push(Injection::new(
+            name,
+            is_positional.then_some(position),
+            &marker,
+        )?);
+    }
+
+    let kind = FunctionKind::of(func)?;
+    let patched = Bound::new(
+        py,
+        InjectedFunction {
+            func: func.clone().unbind(),
+            kind,
+            injections,
+            positional_only,
+        },
+    )?;
+    py.import_bound(intern!(py, "functools"))?
+        .call_method1(intern!(py, "update_wrapper"), (&patched, func))?;
+    if kind == FunctionKind::Coroutine {
+        mark_coroutine_function(patched.as_any())?;
+    }
+    Ok(patched.into_any())
+}
+
+/// Make `asyncio.iscoroutinefunction()` and, on Python 3.12+,
+/// `inspect.iscoroutinefunction()` recognize the patched function.
+fn mark_coroutine_function(patched: &Bound<'_, PyAny>) -> PyResult<()> {
+    let py = patched.py();
+    let inspect = py.import_bound(intern!(py, "inspect"))?;
+    if let Ok(mark) = inspect.getattr(intern!(py, "markcoroutinefunction")) {
+        mark.call1((patched,))?;
+    }
+    let coroutines = py.import_bound(intern!(py, "asyncio.coroutines"))?;
+    if let Ok(is_coroutine) = coroutines.getattr(intern!(py, "_is_coroutine")) {
+        patched.setattr(intern!(py, "_is_coroutine"), is_coroutine)?;
+    }
+    Ok(())
+}
+
+/// Map of declarative container providers to providers of container instance
+pub struct ProvidersMap {
+    container: PyObject,
+    map: HashMap<usize, (PyObject, PyObject)>,
+}
+
+impl ProvidersMap {
+    pub fn new(container: &Bound<'_, DynamicContainer>) -> PyResult<Self> {
+        let py = container.py();
+        let mut map = HashMap::new();
+        let original = match &container.borrow().declarative_parent {
+            Some(cls) => cls.bind(py).clone().into_any(),
+            None => container.clone().into_any(),
+        };
+        Self::collect(container.as_any(), &original, &mut map)?;
+        Ok(Self {
+            container: container.clone().into_any().unbind(),
+            map,
+        })
+    }
+
+    fn collect(
+        current_container: &Bound<'_, PyAny>,
+        original_container: &Bound<'_, PyAny>,
+        map: &mut HashMap<usize, (PyObject, PyObject)>,
+    ) -> PyResult<()> {
+        let py = current_container.py();
+        let current_providers = current_container.getattr(intern!(py, "providers"))?;
+        let original_providers = original_container.getattr(intern!(py, "providers"))?;
+        let original_providers = original_providers.downcast::<PyDict>()?;
+
+        for (name, current_provider) in current_providers.downcast::<PyDict>()?.iter() {
+            let Some(original_provider) = original_providers.get_item(&name)? else {
+                continue;
+            };
+            map.insert(
+                original_provider.as_ptr() as usize,
+                (
+                    original_provider.clone().unbind(),
+                    current_provider.clone().unbind(),
+                ),
+            );
+            map.insert(
+                current_provider.as_ptr() as usize,
+                (
+                    current_provider.clone().unbind(),
+                    current_provider.clone().unbind(),
+                ),
+            );
+
+            if current_provider.is_instance_of::<providers::Container>()
+                && original_provider.is_instance_of::<providers::Container>()
+            {
+                Self::collect(
+                    &current_provider.getattr(intern!(py, "container"))?,
+                    &original_provider.getattr(intern!(py, "container"))?,
+                    map,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Return container provider by its dotted path, e.g. ``services.user_repo``
+    pub fn resolve_path(&self, py: Python, path: &str) -> PyResult<PyObject> {
+        let mut provider = self.container.bind(py).clone();
+        for segment in path.split('.') {
+            provider = provider.getattr(segment)?;
+        }
+        if !provider.is_instance_of::<providers::Provider>() {
+            return Err(pyo3::exceptions::PyTypeError::new_err(format!(
+                "{} is not a provider",
+                provider.repr()?
+            )));
+        }
+        Ok(provider.unbind())
+    }
+
+    /// Return container provider matching original provider.
+    ///
+    /// Chains built with `.provided` are copied over the container provider
+    /// they start from, e.g. ``Provide[Container.config.provided["key"]]``.
+    pub fn resolve_provider(
+        &self,
+        py: Python,
+        original: &Bound<'_, PyAny>,
+    ) -> PyResult<Option<PyObject>> {
+        if let Some((_, current)) = self.map.get(&(original.as_ptr() as usize)) {
+            return Ok(Some(current.clone_ref(py)));
+        }
+        if !self.is_mapped_chain(original)? {
+            return Ok(None);
+        }
+        let memo = PyDict::new_bound(py);
+        for (original, current) in self.map.values() {
+            memo.set_item(original.as_ptr() as usize, current)?;
+        }
+        let copied = py
+            .import_bound(intern!(py, "copy"))?
+            .call_method1(intern!(py, "deepcopy"), (original, memo))?;
+        Ok(Some(copied.unbind()))
+    }
+
+    /// Check if provided instance chain starts from a mapped provider
+    fn is_mapped_chain(&self, provider: &Bound<'_, PyAny>) -> PyResult<bool> {
+        let py = provider.py();
+        let mut provider = provider.clone();
+        while provider.is_instance_of::<providers::ProvidedInstance>() {
+            provider = provider.getattr(intern!(py, "provides"))?;
+            if self.map.contains_key(&(provider.as_ptr() as usize)) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Class attribute injecting provider result on access.
+///
+/// Replaces ``Provide[...]`` marker declared as class attribute while the
+/// module is wired.
+#[pyclass(module = "inj.wiring")]
+pub struct InjectedAttribute {
+    marker: PyObject,
+    injection: Injection,
+}
+
+#[pymethods]
+impl InjectedAttribute {
+    fn __get__(
+        &self,
+        py: Python,
+        _instance: Option<&Bound<'_, PyAny>>,
+        _owner: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<PyObject> {
+        match &self.injection.provider {
+            Some(provider) => self.injection.resolve(py, provider),
+            None => Ok(self.marker.clone_ref(py)),
+        }
+    }
+
+    /// Return marker replaced by the attribute
+    #[getter]
+    fn marker(&self, py: Python) -> PyObject {
+        self.marker.clone_ref(py)
+    }
+
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!("<injected {}>", self.marker.bind(py).repr()?))
+    }
+}
+
+/// Member of a module, which takes part in wiring
+enum Injectable<'py> {
+    Function(Bound<'py, InjectedFunction>),
+    /// Class attribute holding a marker or an injected attribute
+    Attribute {
+        owner: Bound<'py, PyType>,
+        name: Bound<'py, PyString>,
+        value: Bound<'py, PyAny>,
+    },
+}
+
+/// Collect injected functions and marker attributes of the module.
+///
+/// Classes defined in the module are inspected as well: their methods,
+/// class and static methods, property getters and class attributes.
+fn collect_injectables<'py>(module: &Bound<'py, PyModule>) -> PyResult<Vec<Injectable<'py>>> {
+    let py = module.py();
+    let module_name = module.name()?;
+    let mut injectables = Vec::new();
+    for member in module.dict().values() {
+        let member = match member.downcast_into::<InjectedFunction>() {
+            Ok(patched) => {
+                injectables.push(Injectable::Function(patched));
+                continue;
+            }
+            Err(err) => err.into_inner(),
+        };
+        let Ok(cls) = member.downcast_into::<PyType>() else {
+            continue;
+        };
+        let defined_in_module = cls
+            .getattr(intern!(py, "__module__"))
+            .is_ok_and(|name| name.eq(&module_name).unwrap_or(false));
+        if !defined_in_module {
+            continue;
+        }
+        let members = cls.getattr(intern!(py, "__dict__"))?;
+        for item in members.call_method0(intern!(py, "items"))?.iter()? {
+            let (name, value): (Bound<'py, PyString>, Bound<'py, PyAny>) = item?.extract()?;
+            if value.is_instance_of::<Marker>() || value.is_instance_of::<InjectedAttribute>() {
+                injectables.push(Injectable::Attribute {
+                    owner: cls.clone(),
+                    name,
+                    value,
+                });
+            } else if let Some(patched) = method_function(&value)? {
+                injectables.push(Injectable::Function(patched));
+            }
+        }
+    }
+    Ok(injectables)
+}
+
+/// Return injected function of a method, class or static method or property
+fn method_function<'py>(
+    value: &Bound<'py, PyAny>,
+) -> PyResult<Option<Bound<'py, InjectedFunction>>> {
+    let py = value.py();
+    let func = if value.is_instance_of::<InjectedFunction>() {
+        value.clone()
+    } else if value.hasattr(intern!(py, "__func__"))? {
+        value.getattr(intern!(py, "__func__"))?
+    } else if value.hasattr(intern!(py, "fget"))? {
+        value.getattr(intern!(py, "fget"))?
+    } else {
+        return Ok(None);
+    };
+    Ok(func.downcast_into::<InjectedFunction>().ok())
+}
+
+/// Bind injected functions and attributes of the modules to the container
+/// providers
+pub fn wire(
+    container: &Bound<'_, DynamicContainer>,
+    modules: &[Bound<'_, PyModule>],
+) -> PyResult<()> {
+    let providers_map = ProvidersMap::new(container)?;
+    for module in modules {
+        let py = module.py();
+        let module_name = module.name()?.to_string();
+        for injectable in collect_injectables(module)? {
+            match injectable {
+                Injectable::Function(patched) => {
+                    patched
+                        .borrow_mut()
+                        .bind(py, &providers_map, &module_name)?;
+                }
+                Injectable::Attribute { owner, name, value } => {
+                    let attribute = match value.downcast_into::<InjectedAttribute>() {
+                        Ok(attribute) => attribute,
+                        Err(err) => {
+                            let marker = err.into_inner().downcast_into::<Marker>()?;
+                            let injection = Injection::new(name.clone().unbind(), None, &marker)?;
+                            let attribute = Bound::new(
+                                py,
+                                InjectedAttribute {
+                                    marker: marker.into_any().unbind(),
+                                    injection,
+                                },
+                            )?;
+                            owner.setattr(&name, &attribute)?;
+                            attribute
+                        }
+                    };
+                    attribute.borrow_mut().injection.bind(
+                        py,
+                        &providers_map,
+                        "attribute",
+                        &owner.qualname()?,
+                        &module_name,
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Unbind injected functions of the modules and restore attribute markers
+pub fn unwire(modules: &[Bound<'_, PyModule>]) -> PyResult<()> {
+    for module in modules {
+        for injectable in collect_injectables(module)? {
+            match injectable {
+                Injectable::Function(patched) => patched.borrow_mut().unbind(),
+                Injectable::Attribute { owner, name, value } => {
+                    if let Ok(attribute) = value.downcast::<InjectedAttribute>() {
+                        owner.setattr(name, attribute.borrow().marker.clone_ref(module.py()))?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Import module by name, resolving relative names against the package
+pub fn import_module<'py>(
+    py: Python<'py>,
+    name: &str,
+    from_package: Option<&str>,
+) -> PyResult<Bound<'py, PyModule>> {
+    py.import_bound(intern!(py, "importlib"))?
+        .call_method1(intern!(py, "import_module"), (name, from_package))?
+        .downcast_into::<PyModule>()
+        .map_err(Into::into)
+}
+
+/// Return package with all its submodules, recursively
+pub fn walk_package<'py>(package: &Bound<'py, PyModule>) -> PyResult<Vec<Bound<'py, PyModule>>> {
+    let py = package.py();
+    let mut modules = vec![package.clone()];
+    let Ok(path) = package.getattr(intern!(py, "__path__")) else {
+        return Ok(modules);
+    };
+    let prefix = format!("{}.", package.name()?);
+    let walked = py
+        .import_bound(intern!(py, "pkgutil"))?
+        .call_method1(intern!(py, "walk_packages"), (path, prefix))?;
+    for module_info in walked.iter()? {
+        let name: String = module_info?.getattr(intern!(py, "name"))?.extract()?;
+        modules.push(import_module(py, &name, None)?);
+    }
+    Ok(modules)
+}
+
+/// Import hook wiring modules of registered containers' packages on import.
+///
+/// Modules imported after the container was wired, e.g. lazily imported
+/// plugins, are wired the moment they are executed.
+#[pyclass(module = "inj.wiring")]
+pub struct AutoLoader {
+    containers: PyObject,
+}
+
+static AUTO_LOADER: GILOnceCell<Py<AutoLoader>> = GILOnceCell::new();
+
+fn auto_loader(py: Python<'_>) -> PyResult<&Bound<'_, AutoLoader>> {
+    AUTO_LOADER
+        .get_or_try_init(py, || {
+            let containers = py
+                .import_bound(intern!(py, "weakref"))?
+                .getattr(intern!(py, "WeakSet"))?
+                .call0()?;
+            Py::new(
+                py,
+                AutoLoader {
+                    containers: containers.unbind(),
+                },
+            )
+        })
+        .map(|loader| loader.bind(py))
+}
+
+#[pymethods]
+impl AutoLoader {
+    /// Find module spec with the rest of finders, wrapping its loader when
+    /// the module belongs to a package of a registered container.
+    #[pyo3(signature = (fullname, path=None, target=None))]
+    fn find_spec(
+        slf: &Bound<'_, Self>,
+        fullname: &str,
+        path: Option<&Bound<'_, PyAny>>,
+        target: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<PyObject> {
+        let py = slf.py();
+        if slf.borrow().containers_of(py, fullname)?.is_empty() {
+            return Ok(py.None());
+        }
+        let meta_path = py
+            .import_bound(intern!(py, "sys"))?
+            .getattr(intern!(py, "meta_path"))?;
+        for finder in meta_path.iter()? {
+            let finder = finder?;
+            if finder.is(slf) || !finder.hasattr(intern!(py, "find_spec"))? {
+                continue;
+            }
+            let spec = finder.call_method1(intern!(py, "find_spec"), (fullname, path, target))?;
+            if spec.is_none() {
+                continue;
+            }
+            let loader = spec.getattr(intern!(py, "loader"))?;
+            if !loader.is_none() && loader.hasattr(intern!(py, "exec_module"))? {
+                let loader = WiringLoader {
+                    loader: loader.unbind(),
+                };
+                spec.setattr(intern!(py, "loader"), Py::new(py, loader)?)?;
+            }
+            return Ok(spec.unbind());
+        }
+        Ok(py.None())
+    }
+}
+
+impl AutoLoader {
+    /// Return registered containers wired to a package the module belongs to
+    fn containers_of<'py>(
+        &self,
+        py: Python<'py>,
+        module_name: &str,
+    ) -> PyResult<Vec<Bound<'py, DynamicContainer>>> {
+        let mut containers = Vec::new();
+        for container in self.containers.bind(py).iter()? {
+            let Ok(container) = container?.downcast_into::<DynamicContainer>() else {
+                continue;
+            };
+            let is_wired_to = container.borrow().wired_to_packages.iter().any(|package| {
+                module_name == package
+                    || module_name
+                        .strip_prefix(package.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            });
+            if is_wired_to {
+                containers.push(container);
+            }
+        }
+        Ok(containers)
+    }
+}
+
+/// Loader wiring module to containers after executing it
+#[pyclass(module = "inj.wiring")]
+pub struct WiringLoader {
+    loader: PyObject,
+}
+
+#[pymethods]
+impl WiringLoader {
+    fn create_module(&self, py: Python, spec: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+        let loader = self.loader.bind(py);
+        if !loader.hasattr(intern!(py, "create_module"))? {
+            return Ok(py.None());
+        }
+        Ok(loader
+            .call_method1(intern!(py, "create_module"), (spec,))?
+            .unbind())
+    }
+
+    fn exec_module(&self, py: Python, module: &Bound<'_, PyModule>) -> PyResult<()> {
+        self.loader
+            .call_method1(py, intern!(py, "exec_module"), (module,))?;
+        let name = module.name()?.to_string();
+        for container in auto_loader(py)?.borrow().containers_of(py, &name)? {
+            wire(&container, std::slice::from_ref(module))?;
+        }
+        Ok(())
+    }
+
+    fn __getattr__(&self, py: Python, name: &Bound<'_, PyString>) -> PyResult<PyObject> {
+        self.loader.getattr(py, name)
+    }
+}
+
+/// Register container in the import hook
+pub fn register_loader_container(container: &Bound<'_, PyAny>) -> PyResult<()> {
+    let py = container.py();
+    auto_loader(py)?
+        .borrow()
+        .containers
+        .call_method1(py, intern!(py, "add"), (container,))?;
+    Ok(())
+}
+
+/// Register containers in the import hook.
+///
+/// Containers with ``WiringConfiguration(auto_wire=True)`` are registered
+/// automatically on instantiation.
+#[pyfunction]
+#[pyo3(signature = (*containers))]
+pub fn register_loader_containers(containers: &Bound<'_, PyTuple>) -> PyResult<()> {
+    for container in containers.iter() {
+        register_loader_container(&container)?;
+    }
+    Ok(())
+}
+
+/// Unregister containers from the import hook.
+#[pyfunction]
+#[pyo3(signature = (*containers))]
+pub fn unregister_loader_containers(containers: &Bound<'_, PyTuple>) -> PyResult<()> {
+    let py = containers.py();
+    let registered = auto_loader(py)?.borrow().containers.clone_ref(py);
+    for container in containers.iter() {
+        registered.call_method1(py, intern!(py, "discard"), (container,))?;
+    }
+    Ok(())
+}
+
+/// Install import hook wiring modules of registered containers on import.
+#[pyfunction]
+pub fn install_loader(py: Python) -> PyResult<()> {
+    if is_loader_installed(py)? {
+        return Ok(());
+    }
+    py.import_bound(intern!(py, "sys"))?
+        .getattr(intern!(py, "meta_path"))?
+        .call_method1(intern!(py, "insert"), (0, auto_loader(py)?))?;
+    Ok(())
+}
+
+/// Uninstall import hook.
+#[pyfunction]
+pub fn uninstall_loader(py: Python) -> PyResult<()> {
+    if !is_loader_installed(py)? {
+        return Ok(());
+    }
+    py.import_bound(intern!(py, "sys"))?
+        .getattr(intern!(py, "meta_path"))?
+        .call_method1(intern!(py, "remove"), (auto_loader(py)?,))?;
+    Ok(())
+}
+
+/// Check if import hook is installed.
+#[pyfunction]
+pub fn is_loader_installed(py: Python) -> PyResult<bool> {
+    py.import_bound(intern!(py, "sys"))?
+        .getattr(intern!(py, "meta_path"))?
+        .contains(auto_loader(py)?)
+}
+
+/// Register `inj.wiring` submodule
+pub fn add_wiring_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    let wiring = PyModule::new_bound(py, "inj.wiring")?;
+    wiring.add_class::<Marker>()?;
+    wiring.add_class::<Provide>()?;
+    wiring.add_class::<ProviderMarker>()?;
+    wiring.add_class::<Closing>()?;
+    wiring.add_class::<TypeModifier>()?;
+    wiring.add_class::<ProvidedInstance>()?;
+    wiring.add_function(wrap_pyfunction!(as_int, &wiring)?)?;
+    wiring.add_function(wrap_pyfunction!(as_float, &wiring)?)?;
+    wiring.add_function(wrap_pyfunction!(as_, &wiring)?)?;
+    wiring.add_function(wrap_pyfunction!(provided, &wiring)?)?;
+    wiring.add_class::<InjectedFunction>()?;
+    wiring.add_class::<InjectedAttribute>()?;
+    wiring.add_class::<AutoLoader>()?;
+    wiring.add_class::<WiringLoader>()?;
+    wiring.add_function(wrap_pyfunction!(register_loader_containers, &wiring)?)?;
+    wiring.add_function(wrap_pyfunction!(unregister_loader_containers, &wiring)?)?;
+    wiring.add_function(wrap_pyfunction!(install_loader, &wiring)?)?;
+    wiring.add_function(wrap_pyfunction!(uninstall_loader, &wiring)?)?;
+    wiring.add_function(wrap_pyfunction!(is_loader_installed, &wiring)?)?;
+    wiring.add_class::<InjectedGenerator>()?;
+    wiring.add_class::<InjectedAsyncGenerator>()?;
+    wiring.add_function(wrap_pyfunction!(inject, &wiring)?)?;
+
+    m.add("Provide", wiring.getattr("Provide")?)?;
+    m.add("Closing", wiring.getattr("Closing")?)?;
+    m.add("inject", wiring.getattr("inject")?)?;
+    m.add("wiring", &wiring)?;
+    py.import_bound(intern!(py, "sys"))?
+        .getattr(intern!(py, "modules"))?
+        .set_item("inj.wiring", wiring)?;
+    Ok(())
+}
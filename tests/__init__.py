@@ -0,0 +1,8 @@
+"""Tests of the extension module.
+
+Build the module into the import path and run them with::
+
+    cargo build --release
+    cp target/release/libinj.so inj.so
+    python -m unittest discover tests
+"""
@@ -0,0 +1,68 @@
+import unittest
+
+import inj
+
+
+class Handler:
+    def __init__(self, kind, *args, **kwargs):
+        self.kind = kind
+        self.args = args
+        self.kwargs = kwargs
+
+
+def aggregate_container(cls=inj.Aggregate):
+    container = inj.DynamicContainer()
+    container.handlers = cls(
+        csv=inj.Factory(Handler, "csv"),
+        json=inj.Factory(Handler, "json"),
+    )
+    return container
+
+
+class AggregateTest(unittest.TestCase):
+    def test_key_dispatches_with_arguments(self):
+        container = aggregate_container()
+        handler = container.handlers("json", 1, indent=2)
+        self.assertEqual((handler.kind, handler.args, handler.kwargs), ("json", (1,), {"indent": 2}))
+        self.assertEqual(container.handlers("csv").kind, "csv")
+
+    def test_members_are_attributes(self):
+        container = aggregate_container()
+        self.assertEqual(container.handlers.csv().kind, "csv")
+        self.assertIs(container.handlers.json, container.handlers.providers["json"])
+        with self.assertRaises(AttributeError):
+            container.handlers.xml
+
+    def test_set_providers_replaces_members(self):
+        container = aggregate_container()
+        container.handlers.set_providers(xml=inj.Factory(Handler, "xml"))
+        self.assertEqual(list(container.handlers.providers), ["xml"])
+        self.assertEqual(container.handlers("xml").kind, "xml")
+
+    def test_unknown_key(self):
+        container = aggregate_container()
+        with self.assertRaisesRegex(
+            inj.Error, '^Aggregate "handlers" has no "xml" provider, valid keys: "csv", "json"$'
+        ):
+            container.handlers("xml")
+
+    def test_missing_key(self):
+        container = aggregate_container()
+        with self.assertRaisesRegex(
+            inj.Error, '^Aggregate "handlers" missing first argument with the provider key$'
+        ):
+            container.handlers()
+
+    def test_aggregate_is_overridden_as_whole(self):
+        container = aggregate_container()
+        with container.handlers.override(inj.Object("overridden")):
+            self.assertEqual(container.handlers("csv"), "overridden")
+        self.assertEqual(container.handlers("csv").kind, "csv")
+
+    def test_factory_aggregate(self):
+        container = aggregate_container(inj.FactoryAggregate)
+        self.assertEqual(container.handlers("csv").kind, "csv")
+
+
+if __name__ == "__main__":
+    unittest.main()
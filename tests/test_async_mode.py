@@ -0,0 +1,75 @@
+import asyncio
+import unittest
+
+import inj
+
+
+async def awaited(value):
+    await asyncio.sleep(0)
+    return value
+
+
+def pair(first, second):
+    return first, second
+
+
+class AsyncModeTest(unittest.IsolatedAsyncioTestCase):
+    async def test_mode_is_undefined_before_the_first_call(self):
+        factory = inj.Factory(int)
+        self.assertTrue(factory.is_async_mode_undefined())
+
+    async def test_sync_result_disables_async_mode(self):
+        factory = inj.Factory(int)
+        self.assertEqual(factory(), 0)
+        self.assertTrue(factory.is_async_mode_disabled())
+
+    async def test_awaitable_result_enables_async_mode(self):
+        coroutine = inj.Coroutine(awaited, 1)
+        self.assertEqual(await coroutine(), 1)
+        self.assertTrue(coroutine.is_async_mode_enabled())
+
+    async def test_enabled_mode_makes_sync_result_awaitable(self):
+        factory = inj.Factory(int)
+        factory.enable_async_mode()
+        self.assertEqual(await factory(), 0)
+        self.assertTrue(factory.is_async_mode_enabled())
+
+    async def test_disabled_mode_passes_awaitable_injections_as_is(self):
+        coroutine = inj.Coroutine(awaited, 1)
+        factory = inj.Factory(pair, coroutine, 2)
+        factory.disable_async_mode()
+        first, second = factory()
+        self.assertTrue(asyncio.iscoroutine(first))
+        self.assertEqual((await first, second), (1, 2))
+
+    async def test_reset_detects_mode_again(self):
+        factory = inj.Factory(int)
+        factory.enable_async_mode()
+        factory.reset_async_mode()
+        self.assertTrue(factory.is_async_mode_undefined())
+        self.assertEqual(factory(), 0)
+        self.assertTrue(factory.is_async_mode_disabled())
+
+    async def test_sync_factory_awaits_async_injections_concurrently(self):
+        events = []
+
+        async def dependency(name):
+            events.append(("start", name))
+            await asyncio.sleep(0.01)
+            events.append(("end", name))
+            return name
+
+        factory = inj.Factory(
+            pair,
+            inj.Coroutine(dependency, "first"),
+            inj.Coroutine(dependency, "second"),
+        )
+        self.assertEqual(await factory(), ("first", "second"))
+        self.assertTrue(factory.is_async_mode_enabled())
+        self.assertEqual(
+            [event for event, _ in events], ["start", "start", "end", "end"]
+        )
+
+
+if __name__ == "__main__":
+    unittest.main()
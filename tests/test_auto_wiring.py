@@ -0,0 +1,86 @@
+import importlib
+import os
+import shutil
+import sys
+import tempfile
+import unittest
+
+import inj
+from inj import wiring
+
+PACKAGE = "auto_wired_package"
+
+HANDLER_SOURCE = """\
+from inj import Provide, inject
+
+
+@inject
+def handler(value=Provide["value"]):
+    return value
+"""
+
+
+class Container(inj.DeclarativeContainer):
+    wiring_config = inj.WiringConfiguration(packages=[PACKAGE], auto_wire=True)
+
+    value = inj.Object("injected")
+
+
+class AutoWiringTest(unittest.TestCase):
+    def setUp(self):
+        self.path = tempfile.mkdtemp()
+        self.package = os.path.join(self.path, PACKAGE)
+        os.mkdir(self.package)
+        self.write("__init__.py", "")
+        self.write("handlers.py", HANDLER_SOURCE)
+        sys.path.insert(0, self.path)
+        importlib.invalidate_caches()
+
+    def tearDown(self):
+        wiring.uninstall_loader()
+        sys.path.remove(self.path)
+        for name in list(sys.modules):
+            if name == PACKAGE or name.startswith(PACKAGE + "."):
+                del sys.modules[name]
+        shutil.rmtree(self.path)
+
+    def write(self, name, source):
+        with open(os.path.join(self.package, name), "w") as file:
+            file.write(source)
+
+    def test_container_is_wired_on_instantiation(self):
+        container = Container()
+        handlers = importlib.import_module(PACKAGE + ".handlers")
+        self.assertEqual(handlers.handler(), "injected")
+        container.unwire()
+
+    def test_module_imported_later_is_wired_by_loader(self):
+        wiring.install_loader()
+        container = Container()
+        self.write("plugin.py", HANDLER_SOURCE)
+        importlib.invalidate_caches()
+        plugin = importlib.import_module(PACKAGE + ".plugin")
+        self.assertEqual(plugin.handler(), "injected")
+        container.unwire()
+
+    def test_module_imported_later_is_not_wired_without_loader(self):
+        container = Container()
+        self.write("plugin.py", HANDLER_SOURCE)
+        importlib.invalidate_caches()
+        plugin = importlib.import_module(PACKAGE + ".plugin")
+        self.assertIsInstance(plugin.handler(), inj.Provide)
+        container.unwire()
+
+    def test_container_without_auto_wire_is_not_wired(self):
+        class Manual(inj.DeclarativeContainer):
+            wiring_config = inj.WiringConfiguration(packages=[PACKAGE], auto_wire=False)
+
+            value = inj.Object("injected")
+
+        Manual()
+        handlers = importlib.import_module(PACKAGE + ".handlers")
+        self.assertIsInstance(handlers.handler(), inj.Provide)
+
+
+if __name__ == "__main__":
+    unittest.main()
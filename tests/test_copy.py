@@ -0,0 +1,44 @@
+import copy
+import unittest
+
+import inj
+
+
+class DeepCopyTest(unittest.TestCase):
+    def test_injections_are_copied_and_stay_shared(self):
+        shared = inj.Object(1)
+        providers = [
+            inj.Factory(dict, shared, value=shared),
+            inj.Callable(dict, shared, value=shared),
+            inj.Resource(dict, shared, value=shared),
+            inj.Object({}).provided.get.call(shared, shared),
+        ]
+        copied_shared, *copied = copy.deepcopy([shared, *providers])
+        for provider, copied_provider in zip(providers, copied):
+            with self.subTest(provider=type(provider).__name__):
+                self.assertIsNot(copied_provider, provider)
+                self.assertIs(copied_provider.args[0], copied_shared)
+                self.assertEqual(list(copied_provider.kwargs), list(provider.kwargs))
+
+    def test_list_and_dict_injections_are_copied(self):
+        shared = inj.Object(1)
+        copied_shared, copied_list, copied_dict = copy.deepcopy(
+            [shared, inj.List(shared, 2), inj.Dict({1: shared}, value=shared)]
+        )
+        self.assertIs(copied_list.args[0], copied_shared)
+        self.assertEqual(copied_list(), [1, 2])
+        self.assertIs(copied_dict.kwargs["value"], copied_shared)
+        self.assertEqual(copied_dict(), {1: 1, "value": 1})
+
+    def test_copy_keeps_overridings(self):
+        factory = inj.Factory(int)
+        factory.override(inj.Object(5))
+        copied = copy.deepcopy(factory)
+        self.assertEqual(copied(), 5)
+        copied.reset_last_overriding()
+        self.assertEqual(copied(), 0)
+        self.assertEqual(factory(), 5)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,71 @@
+import unittest
+
+import inj
+
+
+def identity(value):
+    return value
+
+
+def pair(first, second):
+    return first, second
+
+
+def failing(value):
+    raise ValueError(value)
+
+
+def cyclic_container():
+    class Container(inj.DeclarativeContainer):
+        a = inj.Factory(identity)
+        b = inj.Factory(identity)
+
+    Container.a.add_args(Container.b)
+    Container.b.add_args(Container.a)
+    return Container()
+
+
+class CycleTest(unittest.TestCase):
+    def test_call_reports_cycle_path(self):
+        container = cyclic_container()
+        with self.assertRaisesRegex(inj.CircularDependencyError, r"^Circular dependency: a -> b -> a$"):
+            container.a()
+        with self.assertRaisesRegex(inj.CircularDependencyError, r"^Circular dependency: b -> a -> b$"):
+            container.b()
+
+    def test_validate_reports_cycle_path(self):
+        with self.assertRaisesRegex(inj.CircularDependencyError, r"^Circular dependency: a -> b -> a$"):
+            cyclic_container().validate()
+
+    def test_resolution_stack_is_restored_after_error(self):
+        container = inj.DynamicContainer()
+        container.value = inj.Object(1)
+        container.failing = inj.Factory(failing, container.value)
+        container.dependent = inj.Factory(identity, container.failing)
+        for _ in range(2):
+            with self.assertRaises(ValueError):
+                container.dependent()
+        container.failing.override(inj.Object(2))
+        self.assertEqual(container.dependent(), 2)
+
+    def test_resolution_stack_is_restored_after_cycle(self):
+        container = cyclic_container()
+        with self.assertRaises(inj.CircularDependencyError):
+            container.a()
+        container.b.override(inj.Object(3))
+        self.assertEqual(container.a(), 3)
+
+    def test_validate_accepts_shared_dependencies(self):
+        container = inj.DynamicContainer()
+        container.shared = inj.Singleton(object)
+        container.left = inj.Factory(identity, container.shared)
+        container.right = inj.Factory(identity, container.shared)
+        container.top = inj.Factory(pair, container.left, container.right)
+        container.other = inj.Factory(pair, container.left, container.shared)
+        container.validate()
+        first, second = container.top()
+        self.assertIs(first, second)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,55 @@
+import unittest
+
+import inj
+
+
+class Pool:
+    def __init__(self, worker_factory):
+        self.worker_factory = worker_factory
+
+    def spawn(self):
+        return self.worker_factory()
+
+
+class Worker:
+    pass
+
+
+class App(inj.DeclarativeContainer):
+    worker = inj.Factory(Worker)
+    pool = inj.Factory(Pool, inj.Delegate(worker))
+    property_pool = inj.Factory(Pool, worker.provider)
+
+
+class DelegateTest(unittest.TestCase):
+    def test_injects_provider_itself(self):
+        app = App()
+        pool = app.pool()
+        self.assertIs(pool.worker_factory, app.worker)
+        self.assertIsInstance(pool.spawn(), Worker)
+        self.assertIsNot(pool.spawn(), pool.spawn())
+
+    def test_provider_property_is_delegate(self):
+        app = App()
+        self.assertIsInstance(app.worker.provider, inj.Delegate)
+        self.assertIs(app.worker.provider(), app.worker)
+        self.assertIs(app.property_pool().worker_factory, app.worker)
+
+    def test_delegate_is_traversable(self):
+        app = App()
+        delegate = app.pool.args[0]
+        self.assertIs(delegate.provides, app.worker)
+        self.assertEqual(delegate.related, [app.worker])
+        self.assertIn(app.worker, list(app.traverse(types=[inj.Factory])))
+        self.assertIn(delegate, list(app.traverse(types=[inj.Delegate])))
+
+    def test_delegated_cycle_is_not_a_cycle(self):
+        container = inj.DynamicContainer()
+        container.pool = inj.Factory(Pool, inj.Object(None))
+        container.pool.set_args(container.pool.provider)
+        container.validate()
+        self.assertIs(container.pool().worker_factory, container.pool)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,92 @@
+import copy
+import unittest
+
+import inj
+
+NAMES = ["kw%02d" % index for index in range(32)][::-1]
+
+
+def keywords(**kwargs):
+    return list(kwargs)
+
+
+class KeywordOrderTest(unittest.TestCase):
+    def test_factory_calls_with_keywords_in_order(self):
+        factory = inj.Factory(keywords, **{name: inj.Object(name) for name in NAMES})
+        self.assertEqual(factory(), NAMES)
+        self.assertEqual(list(factory.kwargs), NAMES)
+
+    def test_added_keywords_keep_order(self):
+        factory = inj.Factory(keywords, **{name: 1 for name in NAMES[:16]})
+        factory.add_kwargs(**{name: 1 for name in NAMES[16:]})
+        self.assertEqual(factory(), NAMES)
+
+    def test_replaced_keyword_keeps_position(self):
+        factory = inj.Factory(keywords, **{name: 1 for name in NAMES})
+        factory.add_kwargs(**{NAMES[0]: 2})
+        self.assertEqual(factory(), NAMES)
+
+    def test_copied_factory_keeps_order(self):
+        factory = inj.Factory(keywords, **{name: 1 for name in NAMES})
+        self.assertEqual(copy.deepcopy(factory)(), NAMES)
+
+    def test_callable_and_method_caller_keep_order(self):
+        kwargs = {name: 1 for name in NAMES}
+        self.assertEqual(inj.Callable(keywords, **kwargs)(), NAMES)
+        self.assertEqual(list(inj.Resource(keywords, **kwargs).kwargs), NAMES)
+        caller = inj.Object(keywords).provided.call(**kwargs)
+        self.assertEqual(caller(), NAMES)
+        self.assertEqual(list(caller.kwargs), NAMES)
+
+
+def call(*args, **kwargs):
+    return args, kwargs
+
+
+class CallPlanTest(unittest.TestCase):
+    def test_call_kwargs_override_injected(self):
+        factory = inj.Factory(call, 1, first=inj.Object(2), second=3)
+        self.assertEqual(factory(4, second=5), ((1, 4), {"first": 2, "second": 5}))
+        self.assertEqual(factory(), ((1,), {"first": 2, "second": 3}))
+
+    def test_args_changes_invalidate_plan(self):
+        factory = inj.Factory(call, 1)
+        self.assertEqual(factory(), ((1,), {}))
+        factory.add_args(inj.Object(2))
+        self.assertEqual(factory(), ((1, 2), {}))
+        factory.set_args(3)
+        self.assertEqual(factory(), ((3,), {}))
+        factory.clear_args()
+        self.assertEqual(factory(), ((), {}))
+
+    def test_kwargs_changes_invalidate_plan(self):
+        factory = inj.Factory(call, first=1)
+        self.assertEqual(factory(), ((), {"first": 1}))
+        factory.add_kwargs(second=inj.Object(2))
+        self.assertEqual(factory(), ((), {"first": 1, "second": 2}))
+        factory.set_kwargs(third=3)
+        self.assertEqual(factory(), ((), {"third": 3}))
+        factory.clear_kwargs()
+        self.assertEqual(factory(), ((), {}))
+
+    def test_override_of_injection_is_used(self):
+        dependency = inj.Factory(int)
+        factory = inj.Factory(call, dependency, key=dependency)
+        self.assertEqual(factory(), ((0,), {"key": 0}))
+        with dependency.override(inj.Object(1)):
+            self.assertEqual(factory(), ((1,), {"key": 1}))
+        self.assertEqual(factory(), ((0,), {"key": 0}))
+
+
+class NoProvidesTest(unittest.TestCase):
+    def test_factory_without_provides(self):
+        with self.assertRaisesRegex(inj.Error, "has no provides"):
+            inj.Factory()()
+
+    def test_singleton_without_provides(self):
+        with self.assertRaisesRegex(inj.Error, "has no provides"):
+            inj.Singleton()()
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,100 @@
+import json
+import unittest
+
+import inj
+
+
+class App(inj.DeclarativeContainer):
+    config = inj.Object("memory")
+    storage = inj.Selector(config, memory=inj.Factory(dict), disk=inj.Factory(list))
+    handlers = inj.Aggregate(csv=inj.Callable(str))
+    service = inj.Factory(set, storage, name=config)
+
+
+DOT = """\
+digraph "App" {
+    node [shape=box];
+    n0 [label="config\\nObject\\n'memory'"];
+    n1 [label="handlers\\nAggregate"];
+    n2 [label="Callable\\nCallable\\nstr"];
+    n3 [label="service\\nFactory\\nset"];
+    n4 [label="storage\\nSelector"];
+    n5 [label="Factory\\nFactory\\nlist"];
+    n6 [label="Factory\\nFactory\\ndict"];
+    n1 -> n2 [label="csv"];
+    n4 -> n0 [label="selector"];
+    n4 -> n5 [label="disk"];
+    n4 -> n6 [label="memory"];
+    n3 -> n4 [label="arg 0"];
+    n3 -> n0 [label="name"];
+}"""
+
+MERMAID = """\
+graph LR
+    n0["config<br/>Object<br/>'memory'"]
+    n1["handlers<br/>Aggregate"]
+    n2["Callable<br/>Callable<br/>str"]
+    n3["service<br/>Factory<br/>set"]
+    n4["storage<br/>Selector"]
+    n5["Factory<br/>Factory<br/>list"]
+    n6["Factory<br/>Factory<br/>dict"]
+    n1 -->|csv| n2
+    n4 -->|selector| n0
+    n4 -->|disk| n5
+    n4 -->|memory| n6
+    n3 -->|arg 0| n4
+    n3 -->|name| n0"""
+
+
+def node(id, name, type, provides):
+    return {"id": id, "name": name, "type": type, "provides": provides}
+
+
+def edge(source, target, kind, name):
+    return {"source": source, "target": target, "kind": kind, "name": name}
+
+
+JSON = {
+    "name": "App",
+    "nodes": [
+        node("n0", "config", "Object", "'memory'"),
+        node("n1", "handlers", "Aggregate", None),
+        node("n2", "Callable", "Callable", "str"),
+        node("n3", "service", "Factory", "set"),
+        node("n4", "storage", "Selector", None),
+        node("n5", "Factory", "Factory", "list"),
+        node("n6", "Factory", "Factory", "dict"),
+    ],
+    "edges": [
+        edge("n1", "n2", "branch", "csv"),
+        edge("n4", "n0", "selector", None),
+        edge("n4", "n5", "branch", "disk"),
+        edge("n4", "n6", "branch", "memory"),
+        edge("n3", "n4", "arg", "0"),
+        edge("n3", "n0", "kwarg", "name"),
+    ],
+}
+
+
+class GraphTest(unittest.TestCase):
+    def setUp(self):
+        self.graph = App().graph()
+
+    def test_nodes_and_edges(self):
+        self.assertEqual(self.graph.name, "App")
+        self.assertEqual(self.graph.nodes, JSON["nodes"])
+        self.assertEqual(self.graph.edges, JSON["edges"])
+
+    def test_to_dot(self):
+        self.assertEqual(self.graph.to_dot(), DOT)
+
+    def test_to_mermaid(self):
+        self.assertEqual(self.graph.to_mermaid(), MERMAID)
+
+    def test_to_json(self):
+        self.assertEqual(json.loads(self.graph.to_json()), JSON)
+        self.assertEqual(self.graph.to_json(indent=2), json.dumps(JSON, indent=2))
+
+
+if __name__ == "__main__":
+    unittest.main()
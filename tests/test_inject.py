@@ -0,0 +1,84 @@
+import asyncio
+import sys
+import unittest
+
+import inj
+from inj import Provide, inject
+
+
+class Container(inj.DeclarativeContainer):
+    value = inj.Object(42)
+    other = inj.Object("other")
+    coroutine = inj.Callable(asyncio.sleep, 0, "awaited")
+
+
+@inject
+def positional_only(value=Provide[Container.value], /):
+    return value
+
+
+@inject
+def positional_only_gap(value=Provide[Container.value], default=1, other=Provide[Container.other], /):
+    return value, default, other
+
+
+@inject
+def mixed(value=Provide[Container.value], /, other=Provide[Container.other]):
+    return value, other
+
+
+@inject
+async def async_positional_only(value=Provide[Container.coroutine], /):
+    return value
+
+
+def rewire():
+    container.unwire()
+    container.wire(modules=[sys.modules[__name__]])
+    return 1
+
+
+class Rewiring(inj.DeclarativeContainer):
+    rewired = inj.Callable(rewire)
+
+
+@inject
+def rewiring(rewired=Provide[Rewiring.rewired]):
+    return rewired
+
+
+class InjectTest(unittest.TestCase):
+    def setUp(self):
+        global container
+        container = Container()
+        container.wire(modules=[sys.modules[__name__]])
+
+    def tearDown(self):
+        container.unwire()
+
+    def test_positional_only_parameter(self):
+        self.assertEqual(positional_only(), 42)
+        self.assertEqual(positional_only(1), 1)
+
+    def test_positional_only_parameters_keep_skipped_defaults(self):
+        self.assertEqual(positional_only_gap(), (42, 1, "other"))
+        self.assertEqual(positional_only_gap(0, 2), (0, 2, "other"))
+
+    def test_positional_only_with_keyword_parameter(self):
+        self.assertEqual(mixed(), (42, "other"))
+        self.assertEqual(mixed(other=2), (42, 2))
+
+    def test_awaited_positional_only_parameter(self):
+        self.assertEqual(asyncio.run(async_positional_only()), "awaited")
+
+    def test_provider_rewires_during_injection(self):
+        rewiring_container = Rewiring()
+        rewiring_container.wire(modules=[sys.modules[__name__]])
+        try:
+            self.assertEqual(rewiring(), 1)
+        finally:
+            rewiring_container.unwire()
+
+
+if __name__ == "__main__":
+    unittest.main()
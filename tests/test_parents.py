@@ -0,0 +1,55 @@
+import unittest
+
+import inj
+
+
+class AppContainer(inj.DynamicContainer):
+    pass
+
+
+class DependenciesContainerTest(unittest.TestCase):
+    def test_constructor_dependencies_get_parent(self):
+        dependencies = inj.DependenciesContainer(database=inj.Dependency())
+        self.assertIs(dependencies.database.parent, dependencies)
+
+    def test_subclass_constructor_dependencies_get_parent(self):
+        class Dependencies(inj.DependenciesContainer):
+            pass
+
+        dependencies = Dependencies(database=inj.Dependency())
+        self.assertIs(dependencies.database.parent, dependencies)
+
+    def test_parent_name_of_constructor_dependency(self):
+        container = inj.DynamicContainer()
+        container.gateways = inj.DependenciesContainer(database=inj.Dependency())
+        self.assertEqual(container.gateways.database.parent_name, "gateways.database")
+        with self.assertRaisesRegex(inj.Error, "gateways.database"):
+            container.gateways.database()
+
+
+class ErrorLabelTest(unittest.TestCase):
+    def test_root_container_is_labelled_with_its_class(self):
+        container = AppContainer()
+        container.database = inj.Dependency()
+        with self.assertRaisesRegex(inj.Error, '^Container "AppContainer" has undefined'):
+            container.check_dependencies()
+
+    def test_declarative_container_is_labelled_with_its_class(self):
+        class AppContainer(inj.DeclarativeContainer):
+            provider_type = inj.Factory
+
+        with self.assertRaisesRegex(inj.Error, '^Container "AppContainer" can contain only'):
+            AppContainer().other = inj.Object(1)
+
+    def test_getters_without_name_are_labelled(self):
+        container = inj.DynamicContainer()
+        container.attribute = inj.AttributeGetter(inj.Object(1))
+        container.item = inj.ItemGetter(inj.Object(1))
+        with self.assertRaisesRegex(inj.Error, '^Attribute getter "attribute" has no name$'):
+            container.attribute()
+        with self.assertRaisesRegex(inj.Error, '^Item getter "item" has no name$'):
+            container.item()
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,137 @@
+import asyncio
+import threading
+import unittest
+
+import inj
+
+
+def container_with_log():
+    log = []
+
+    def resource(name):
+        log.append(("open", name))
+        yield name
+        log.append(("close", name))
+
+    async def async_resource(name):
+        log.append(("open", name))
+        yield name
+        await asyncio.sleep(0)
+        log.append(("close", name))
+
+    container = inj.DynamicContainer()
+    container.first = inj.Scoped("request", resource, "first")
+    container.second = inj.Scoped("request", resource, "second")
+    container.async_first = inj.Scoped("request", async_resource, "first")
+    container.async_second = inj.Scoped("request", async_resource, "second")
+    container.session = inj.Scoped("session", object)
+    container.request = inj.Scoped("request", object)
+    return container, log
+
+
+class ScopeTest(unittest.TestCase):
+    def test_instance_is_cached_in_scope(self):
+        container, _ = container_with_log()
+        with container.scope("request"):
+            request = container.request()
+            self.assertIs(container.request(), request)
+        with container.scope("request"):
+            self.assertIsNot(container.request(), request)
+
+    def test_scope_must_be_entered(self):
+        container, _ = container_with_log()
+        with self.assertRaisesRegex(inj.Error, "requires scope"):
+            container.request()
+
+    def test_generators_are_closed_in_reverse_order(self):
+        container, log = container_with_log()
+        with container.scope("request"):
+            self.assertEqual(container.first(), "first")
+            self.assertEqual(container.second(), "second")
+            self.assertEqual(container.first(), "first")
+            self.assertEqual(log, [("open", "first"), ("open", "second")])
+        self.assertEqual(log[2:], [("close", "second"), ("close", "first")])
+
+    def test_nested_scope_of_another_name_shares_instance(self):
+        container, _ = container_with_log()
+        with container.scope("session"):
+            session = container.session()
+            with container.scope("request"):
+                self.assertIs(container.session(), session)
+
+    def test_nested_scope_of_the_same_name_gets_own_instance(self):
+        container, log = container_with_log()
+        with container.scope("request"):
+            outer = container.request()
+            container.first()
+            with container.scope("request"):
+                inner = container.request()
+                self.assertIsNot(inner, outer)
+                self.assertIs(container.request(), inner)
+                container.first()
+            self.assertEqual(log[-1], ("close", "first"))
+            self.assertIs(container.request(), outer)
+        self.assertEqual(len(log), 4)
+
+    def test_new_thread_does_not_see_scope(self):
+        container, _ = container_with_log()
+        errors = []
+
+        def provide():
+            try:
+                container.request()
+            except inj.Error as err:
+                errors.append(err)
+
+        with container.scope("request"):
+            thread = threading.Thread(target=provide)
+            thread.start()
+            thread.join()
+        self.assertEqual(len(errors), 1)
+
+
+class AsyncScopeTest(unittest.IsolatedAsyncioTestCase):
+    async def test_async_generators_are_closed_in_reverse_order(self):
+        container, log = container_with_log()
+        async with container.scope("request"):
+            self.assertEqual(await container.async_first(), "first")
+            self.assertEqual(await container.async_second(), "second")
+            self.assertEqual(await container.async_first(), "first")
+        self.assertEqual(
+            log,
+            [
+                ("open", "first"),
+                ("open", "second"),
+                ("close", "second"),
+                ("close", "first"),
+            ],
+        )
+
+    async def test_sync_exit_rejects_async_resources(self):
+        container, _ = container_with_log()
+        with self.assertRaisesRegex(inj.Error, "async with"):
+            with container.scope("request"):
+                await container.async_first()
+
+    async def test_tasks_share_scope(self):
+        container, _ = container_with_log()
+        async with container.scope("request"):
+            request = container.request()
+            self.assertIs(await asyncio.create_task(self.provide(container)), request)
+
+    async def test_nested_async_scopes(self):
+        container, log = container_with_log()
+        async with container.scope("request"):
+            await container.async_first()
+            async with container.scope("request"):
+                await container.async_first()
+            self.assertEqual(log[-1], ("close", "first"))
+        self.assertEqual(len(log), 4)
+
+    @staticmethod
+    async def provide(container):
+        return container.request()
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,82 @@
+import unittest
+
+import inj
+
+
+class Storage:
+    def __init__(self, kind, *args, **kwargs):
+        self.kind = kind
+        self.args = args
+        self.kwargs = kwargs
+
+
+class Environment:
+    def __init__(self, value):
+        self.value = value
+
+    def __call__(self):
+        return self.value
+
+
+def selector_container(key):
+    environment = Environment(key)
+    container = inj.DynamicContainer()
+    container.storage = inj.Selector(
+        environment,
+        memory=inj.Factory(Storage, "memory"),
+        disk=inj.Factory(Storage, "disk"),
+    )
+    return container, environment
+
+
+class SelectorTest(unittest.TestCase):
+    def test_branch_is_selected_on_call(self):
+        container, environment = selector_container("memory")
+        self.assertEqual(container.storage().kind, "memory")
+        environment.value = "disk"
+        self.assertEqual(container.storage().kind, "disk")
+
+    def test_call_arguments_are_forwarded(self):
+        container, _ = selector_container("disk")
+        storage = container.storage(1, path="/tmp")
+        self.assertEqual((storage.kind, storage.args, storage.kwargs), ("disk", (1,), {"path": "/tmp"}))
+
+    def test_branches_are_attributes(self):
+        container, _ = selector_container("memory")
+        self.assertEqual(container.storage.disk().kind, "disk")
+        with self.assertRaises(AttributeError):
+            container.storage.network
+
+    def test_branch_is_overridden_individually(self):
+        container, environment = selector_container("memory")
+        with container.storage.memory.override(inj.Object("overridden")):
+            self.assertEqual(container.storage(), "overridden")
+            environment.value = "disk"
+            self.assertEqual(container.storage().kind, "disk")
+        environment.value = "memory"
+        self.assertEqual(container.storage().kind, "memory")
+
+    def test_undefined_key(self):
+        container, _ = selector_container(None)
+        with self.assertRaisesRegex(
+            inj.Error, '^Selector "storage" value is undefined, valid keys: "disk", "memory"$'
+        ):
+            container.storage()
+
+    def test_unknown_key(self):
+        container, _ = selector_container("network")
+        with self.assertRaisesRegex(
+            inj.Error,
+            '^Selector "storage" has no "network" provider, valid keys: "disk", "memory"$',
+        ):
+            container.storage()
+
+    def test_missing_selector(self):
+        container = inj.DynamicContainer()
+        container.storage = inj.Selector(memory=inj.Object(1))
+        with self.assertRaisesRegex(inj.Error, '^Selector "storage" has no selector$'):
+            container.storage()
+
+
+if __name__ == "__main__":
+    unittest.main()
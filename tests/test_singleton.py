@@ -0,0 +1,95 @@
+import asyncio
+import gc
+import unittest
+
+import inj
+
+
+class Counter:
+    def __init__(self, fail_first=False):
+        self.calls = 0
+        self.fail_first = fail_first
+
+    async def __call__(self):
+        self.calls += 1
+        await asyncio.sleep(0.01)
+        if self.fail_first and self.calls == 1:
+            raise ValueError("first call fails")
+        return object()
+
+
+class AsyncSingletonTest(unittest.IsolatedAsyncioTestCase):
+    async def test_concurrent_first_awaits_share_initialization(self):
+        counter = Counter()
+        singleton = inj.Singleton(counter)
+        instances = await asyncio.gather(singleton(), singleton(), singleton())
+        self.assertEqual(counter.calls, 1)
+        self.assertTrue(all(instance is instances[0] for instance in instances))
+        self.assertIs(await singleton(), instances[0])
+
+    async def test_failed_initialization_is_shared_and_retried(self):
+        counter = Counter(fail_first=True)
+        singleton = inj.Singleton(counter)
+        results = await asyncio.gather(singleton(), singleton(), return_exceptions=True)
+        self.assertEqual(counter.calls, 1)
+        for result in results:
+            self.assertIsInstance(result, ValueError)
+        instance = await singleton()
+        self.assertEqual(counter.calls, 2)
+        self.assertIs(await singleton(), instance)
+
+    async def test_closed_first_awaitable_wakes_waiters(self):
+        counter = Counter()
+        singleton = inj.Singleton(counter)
+        first = singleton()
+        first.send(None)
+        waiting = asyncio.ensure_future(singleton())
+        await asyncio.sleep(0)
+        first.close()
+        with self.assertRaisesRegex(inj.Error, "abandoned"):
+            await waiting
+        self.assertIsNotNone(await singleton())
+        self.assertEqual(counter.calls, 2)
+
+    async def test_dropped_first_awaitable_wakes_waiters(self):
+        counter = Counter()
+        singleton = inj.Singleton(counter)
+        first = singleton()
+        first.send(None)
+        waiting = asyncio.ensure_future(singleton())
+        await asyncio.sleep(0)
+        del first
+        gc.collect()
+        with self.assertRaisesRegex(inj.Error, "abandoned"):
+            await waiting
+        self.assertIsNotNone(await singleton())
+
+    async def test_cancelled_initialization_wakes_waiters(self):
+        singleton = inj.Singleton(Counter())
+        first = asyncio.ensure_future(singleton())
+        await asyncio.sleep(0)
+        waiting = asyncio.ensure_future(singleton())
+        await asyncio.sleep(0)
+        first.cancel()
+        with self.assertRaises(asyncio.CancelledError):
+            await waiting
+        self.assertIsNotNone(await singleton())
+
+    async def test_reset_during_initialization(self):
+        counter = Counter()
+        singleton = inj.Singleton(counter)
+        first = asyncio.ensure_future(singleton())
+        await asyncio.sleep(0)
+        waiting = asyncio.ensure_future(singleton())
+        await asyncio.sleep(0)
+        singleton.reset()
+        with self.assertRaisesRegex(inj.Error, "reset"):
+            await waiting
+        stale = await first
+        fresh = await singleton()
+        self.assertIsNot(fresh, stale)
+        self.assertEqual(counter.calls, 2)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,85 @@
+import unittest
+
+import inj
+
+
+def identity(value):
+    return value
+
+
+class App(inj.DeclarativeContainer):
+    container = inj.Self()
+    value = inj.Object({"key": 1})
+    counter = inj.Factory(list)
+    items = inj.List(value, counter, 3)
+    mapping = inj.Dict(value=value, counter=counter, literal=3)
+    injected = inj.Callable(identity, container)
+
+
+class ObjectTest(unittest.TestCase):
+    def test_returns_given_value(self):
+        app = App()
+        self.assertIs(app.value(), app.value())
+        self.assertEqual(app.value(), {"key": 1})
+
+
+class SelfTest(unittest.TestCase):
+    def test_resolves_owning_container(self):
+        app = App()
+        self.assertIs(app.container(), app)
+        self.assertIs(app.__self__(), app)
+        self.assertIs(app.injected(), app)
+        self.assertIs(app.container, app.__self__)
+
+    def test_declarative_container_resolves_to_class(self):
+        self.assertIs(App.container(), App)
+
+    def test_every_container_instance_gets_own_self(self):
+        first, second = App(), App()
+        self.assertIs(first.container(), first)
+        self.assertIs(second.container(), second)
+
+
+class CollectionTest(unittest.TestCase):
+    def test_list_resolves_elements_on_call(self):
+        app = App()
+        first, second = app.items(), app.items()
+        self.assertEqual(first, [{"key": 1}, [], 3])
+        self.assertIsNot(first[1], second[1])
+
+    def test_dict_resolves_values_on_call(self):
+        app = App()
+        self.assertEqual(app.mapping(), {"value": {"key": 1}, "counter": [], "literal": 3})
+        with app.value.override(inj.Object(2)):
+            self.assertEqual(app.mapping()["value"], 2)
+            self.assertEqual(app.items()[0], 2)
+
+    def test_collections_are_related_to_their_providers(self):
+        app = App()
+        self.assertEqual(app.items.related, [app.value, app.counter])
+        self.assertEqual(app.mapping.related, [app.value, app.counter])
+        self.assertIn(app.items, list(app.traverse(types=[inj.List])))
+
+
+class SchemaTest(unittest.TestCase):
+    def test_structural_providers_from_schema(self):
+        container = inj.DynamicContainer()
+        container.from_schema(
+            {
+                "version": "1",
+                "container": {
+                    "separator": {"provider": "Object", "provides": "os.sep"},
+                    "number": {"provider": "Factory", "provides": "builtins.int", "args": [7]},
+                    "items": {"provider": "List", "args": ["container.separator", "container.number"]},
+                    "mapping": {"provider": "Dict", "kwargs": {"number": "container.number"}},
+                    "me": {"provider": "Self"},
+                },
+            }
+        )
+        self.assertEqual(container.items(), [container.separator(), 7])
+        self.assertEqual(container.mapping(), {"number": 7})
+        self.assertIs(container.me(), container)
+
+
+if __name__ == "__main__":
+    unittest.main()
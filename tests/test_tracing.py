@@ -0,0 +1,196 @@
+import gc
+import logging
+import re
+import unittest
+import weakref
+
+import inj
+from inj import tracing
+
+
+def failing():
+    raise ValueError("100% broken")
+
+
+def identity(value):
+    return value
+
+
+class Gateways(inj.DeclarativeContainer):
+    database = inj.Factory(object)
+
+
+class App(inj.DeclarativeContainer):
+    gateways = inj.Container(Gateways)
+    service = inj.Factory(identity, gateways.database)
+
+
+def pair(first, second):
+    return first, second
+
+
+class Collector:
+    def __init__(self):
+        self.events = []
+
+    def collect(self, event):
+        self.events.append(event)
+
+
+class LoggingHookTest(unittest.TestCase):
+    def test_error_with_percent_sign_is_logged(self):
+        logger = logging.getLogger("inj.tests.tracing")
+        container = inj.DynamicContainer()
+        container.failing = inj.Factory(failing)
+        container.add_resolution_hook(tracing.LoggingHook(logger))
+        with self.assertLogs(logger, logging.WARNING) as logs:
+            with self.assertRaises(ValueError):
+                container.failing()
+        self.assertEqual(len(logs.records), 1)
+        self.assertIn("ValueError('100% broken')", logs.records[0].getMessage())
+
+
+class ContainerHookTest(unittest.TestCase):
+    def test_hook_observes_providers_and_their_dependencies(self):
+        collector = Collector()
+        value = inj.Object(1)
+        container = inj.DynamicContainer()
+        container.dependent = inj.Factory(identity, value)
+        container.add_resolution_hook(collector.collect)
+        self.assertEqual(container.dependent(), 1)
+        self.assertEqual(
+            [event.provider for event in collector.events],
+            [value, container.dependent],
+        )
+        value()
+        self.assertEqual(len(collector.events), 2)
+
+    def test_bound_method_hook_is_removed(self):
+        collector = Collector()
+        container = inj.DynamicContainer()
+        container.value = inj.Object(1)
+        container.add_resolution_hook(collector.collect)
+        container.remove_resolution_hook(collector.collect)
+        container.value()
+        self.assertEqual(collector.events, [])
+
+    def test_container_is_collected_with_registered_hook(self):
+        container = inj.DynamicContainer()
+        container.value = inj.Object(1)
+        container.add_resolution_hook(Collector().collect)
+        reference = weakref.ref(container)
+        del container
+        gc.collect()
+        self.assertIsNone(reference())
+
+
+class GlobalHookTest(unittest.TestCase):
+    def test_bound_method_hook_is_removed(self):
+        collector = Collector()
+        tracing.add_resolution_hook(collector.collect)
+        try:
+            inj.Object(1)()
+        finally:
+            tracing.remove_resolution_hook(collector.collect)
+        inj.Object(2)()
+        self.assertEqual(len(collector.events), 1)
+
+
+class EventPathTest(unittest.TestCase):
+    def test_path_of_nested_container_provider(self):
+        collector = Collector()
+        app = App()
+        tracing.add_resolution_hook(collector.collect)
+        try:
+            app.gateways.database()
+            app.service()
+        finally:
+            tracing.remove_resolution_hook(collector.collect)
+        self.assertEqual(
+            [(event.path, event.depth) for event in collector.events],
+            [
+                ("App.gateways.database", 0),
+                ("App.gateways.database", 1),
+                ("App.service", 0),
+            ],
+        )
+
+    def test_path_of_dynamic_container_provider(self):
+        collector = Collector()
+        container = inj.DynamicContainer()
+        container.value = inj.Object(1)
+        container.add_resolution_hook(collector.collect)
+        container.value()
+        self.assertEqual(collector.events[0].path, "DynamicContainer.value")
+
+
+class ResolutionRecorderTest(unittest.TestCase):
+    def setUp(self):
+        self.container = inj.DynamicContainer()
+        self.container.value = inj.Object(1)
+        self.container.shared = inj.Singleton(identity, self.container.value)
+        self.container.top = inj.Factory(pair, self.container.shared, self.container.value)
+        self.recorder = tracing.ResolutionRecorder()
+        self.container.add_resolution_hook(self.recorder)
+
+    def shape(self, node):
+        return (node["path"], node["cached"], [self.shape(child) for child in node["children"]])
+
+    def test_calls_tree(self):
+        self.container.top()
+        self.container.top()
+        self.assertEqual(
+            [self.shape(call) for call in self.recorder.calls],
+            [
+                (
+                    "DynamicContainer.top",
+                    False,
+                    [
+                        ("DynamicContainer.shared", False, [("DynamicContainer.value", False, [])]),
+                        ("DynamicContainer.value", False, []),
+                    ],
+                ),
+                (
+                    "DynamicContainer.top",
+                    False,
+                    [
+                        ("DynamicContainer.shared", True, []),
+                        ("DynamicContainer.value", False, []),
+                    ],
+                ),
+            ],
+        )
+        self.recorder.clear()
+        self.assertEqual(self.recorder.calls, [])
+
+    def test_format(self):
+        self.container.top()
+        self.container.top()
+        formatted = re.sub(r"\d+\.\d{3} ms", "T ms", self.recorder.format())
+        self.assertEqual(
+            formatted,
+            "\n".join(
+                [
+                    "DynamicContainer.top T ms",
+                    "  DynamicContainer.shared T ms",
+                    "    DynamicContainer.value T ms",
+                    "  DynamicContainer.value T ms",
+                    "DynamicContainer.top T ms",
+                    "  DynamicContainer.shared T ms (cached)",
+                    "  DynamicContainer.value T ms",
+                ]
+            ),
+        )
+
+    def test_format_failure(self):
+        self.container.failing = inj.Factory(failing)
+        with self.assertRaises(ValueError):
+            self.container.failing()
+        formatted = re.sub(r"\d+\.\d{3} ms", "T ms", self.recorder.format())
+        self.assertEqual(
+            formatted, "DynamicContainer.failing T ms failed: ValueError('100% broken')"
+        )
+
+
+if __name__ == "__main__":
+    unittest.main()
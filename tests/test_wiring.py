@@ -0,0 +1,182 @@
+import sys
+import types
+import unittest
+
+import inj
+from inj import Provide, inject
+from inj.wiring import Provider, as_int, provided
+
+
+class Client:
+    def __init__(self, name):
+        self.name = name
+
+    def greet(self, greeting):
+        return "%s, %s" % (greeting, self.name)
+
+
+class Gateways(inj.DeclarativeContainer):
+    database = inj.Object("database")
+
+
+class Container(inj.DeclarativeContainer):
+    config = inj.Object({"key": 1, "nested": {"inner": 2}})
+    client = inj.Factory(Client, "client")
+    timeout = inj.Object("5")
+    gateways = inj.Container(Gateways)
+
+
+def make_module(name, source):
+    module = types.ModuleType(name)
+    exec(source, module.__dict__)
+    return module
+
+
+@inject
+def provided_item(value=Provide[Container.config.provided["key"]]):
+    return value
+
+
+@inject
+def provided_nested_item(value=Provide[Container.config.provided["nested"]["inner"]]):
+    return value
+
+
+@inject
+def provided_method_call(value=Provide[Container.client.provided.greet.call("hello")]):
+    return value
+
+
+@inject
+def provided_attribute(value=Provide[Container.client.provided.name]):
+    return value
+
+
+@inject
+def string_marker(client=Provide["client"]):
+    return client
+
+
+@inject
+def nested_string_marker(database=Provide["gateways.database"]):
+    return database
+
+
+@inject
+def string_marker_with_modifiers(
+    timeout=Provide["timeout", as_int()],
+    key=Provide["config", provided()["nested"]["inner"]],
+):
+    return timeout, key
+
+
+@inject
+def string_provider_marker(client=Provider["client"]):
+    return client
+
+
+class Service:
+    client = Provide[Container.client]
+    timeout = Provide["timeout", as_int()]
+
+    @inject
+    def method(self, value=Provide[Container.timeout]):
+        return self, value
+
+    @classmethod
+    @inject
+    def class_method(cls, value=Provide[Container.timeout]):
+        return cls, value
+
+    @staticmethod
+    @inject
+    def static_method(value=Provide["gateways.database"]):
+        return value
+
+    @property
+    @inject
+    def prop(self, value=Provide[Container.client.provided.name]):
+        return value
+
+
+class WiringTest(unittest.TestCase):
+    def setUp(self):
+        self.container = Container()
+        self.container.wire(modules=[sys.modules[__name__]])
+
+    def tearDown(self):
+        self.container.unwire()
+
+
+class ProvidedChainTest(WiringTest):
+    def test_item_chain(self):
+        self.assertEqual(provided_item(), 1)
+        self.assertEqual(provided_nested_item(), 2)
+
+    def test_attribute_and_method_call_chain(self):
+        self.assertEqual(provided_attribute(), "client")
+        self.assertEqual(provided_method_call(), "hello, client")
+
+    def test_chain_is_built_on_container_provider(self):
+        with self.container.config.override(inj.Object({"key": 3})):
+            self.assertEqual(provided_item(), 3)
+        with self.container.client.override(inj.Factory(Client, "other")):
+            self.assertEqual(provided_method_call(), "hello, other")
+
+
+class StringMarkerTest(WiringTest):
+    def test_marker_is_resolved_against_container(self):
+        self.assertEqual(string_marker().name, "client")
+        self.assertEqual(nested_string_marker(), "database")
+        with self.container.client.override(inj.Object("overridden")):
+            self.assertEqual(string_marker(), "overridden")
+
+    def test_modifiers(self):
+        self.assertEqual(string_marker_with_modifiers(), (5, 2))
+
+    def test_provider_marker(self):
+        self.assertIs(string_provider_marker(), self.container.client)
+
+    def test_unresolved_marker_raises_at_wire_time(self):
+        module = make_module(
+            "unresolved_markers",
+            "from inj import Provide, inject\n"
+            "@inject\n"
+            "def handler(repository=Provide['services.missing']):\n"
+            "    return repository\n",
+        )
+        container = Container()
+        with self.assertRaisesRegex(
+            inj.Error,
+            'provider "services.missing" for parameter "repository" of "handler" '
+            'in module "unresolved_markers"',
+        ):
+            container.wire(modules=[module])
+
+
+class ClassMemberTest(WiringTest):
+    def test_methods(self):
+        service = Service()
+        self.assertEqual(service.method(), (service, "5"))
+        self.assertEqual(service.method("given"), (service, "given"))
+        self.assertEqual(Service.class_method(), (Service, "5"))
+        self.assertEqual(service.class_method(), (Service, "5"))
+        self.assertEqual(Service.static_method(), "database")
+
+    def test_property(self):
+        self.assertEqual(Service().prop, "client")
+
+    def test_class_attributes(self):
+        self.assertEqual(Service.client.name, "client")
+        self.assertIsNot(Service.client, Service().client)
+        self.assertEqual(Service().timeout, 5)
+
+    def test_class_attributes_are_markers_after_unwire(self):
+        self.container.unwire()
+        self.assertIsInstance(Service.client, inj.Provide)
+        self.container.wire(modules=[sys.modules[__name__]])
+        self.assertEqual(Service.client.name, "client")
+
+
+if __name__ == "__main__":
+    unittest.main()